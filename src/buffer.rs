@@ -0,0 +1,143 @@
+// Temporary buffers that are shown in place of the output, like the help screen
+//
+// While a temporary buffer is shown, the output and everything tied to it is put
+// aside in a `SavedContent`. Data that the application sends in the meantime is
+// added to the saved output, so that nothing is lost when it is restored.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{wrap_str, Pager};
+
+// The output and the state tied to it, put aside while a temporary buffer is shown
+pub(crate) struct SavedContent {
+    pub(crate) wrap_lines: Vec<Vec<String>>,
+    pub(crate) text_lines: Vec<String>,
+    pub(crate) prompt: Vec<String>,
+    // The position as the logical line at the top of the screen and the row
+    // within that line, so that it survives rewrapping
+    line: usize,
+    row_in_line: usize,
+    marks: HashMap<char, usize>,
+    focused_line: Option<usize>,
+    selection: Option<(usize, usize)>,
+    #[cfg(feature = "search")]
+    search_term: Option<crate::search::SearchTerm>,
+    #[cfg(feature = "search")]
+    search_mark: usize,
+    // For the occur view, the line of the output listed on each line, as
+    // numbered in the whole output
+    #[cfg(feature = "search")]
+    pub(crate) occur: Option<Vec<usize>>,
+}
+
+// Show `text` in place of the output with `prompt` at the prompt line
+//
+// Returns false without doing anything if a temporary buffer is already shown
+pub(crate) fn show(pager: &mut Pager, text: &str, prompt: &str) -> bool {
+    if pager.saved_content.is_some() {
+        return false;
+    }
+    let line = pager.line_of_row(pager.upper_mark);
+    let cols = pager.cols;
+    let saved = SavedContent {
+        wrap_lines: std::mem::replace(
+            &mut pager.wrap_lines,
+            text.lines().map(|l| wrap_str(l, cols)).collect(),
+        ),
+        text_lines: std::mem::replace(
+            &mut pager.text_lines,
+            text.lines().map(ToString::to_string).collect(),
+        ),
+        prompt: std::mem::replace(&mut pager.prompt, wrap_str(prompt, cols)),
+        line,
+        row_in_line: pager.upper_mark.saturating_sub(pager.row_of_line(line)),
+        marks: std::mem::take(&mut pager.marks),
+        focused_line: pager.focused_line.take(),
+        selection: pager.selection.take(),
+        #[cfg(feature = "search")]
+        search_term: pager.search_term.take(),
+        #[cfg(feature = "search")]
+        search_mark: std::mem::take(&mut pager.search_mark),
+        #[cfg(feature = "search")]
+        occur: None,
+    };
+    pager.saved_content = Some(saved);
+    pager.upper_mark = 0;
+    // The matches are found again when the output is restored
+    #[cfg(feature = "search")]
+    {
+        pager.search_idx.clear();
+        pager.search_indexing = None;
+    }
+    true
+}
+
+// Put the output back in place of the temporary buffer, at the position it was
+// left at
+//
+// Returns false if no temporary buffer is shown
+pub(crate) fn restore(pager: &mut Pager) -> bool {
+    let Some(saved) = pager.saved_content.take() else {
+        return false;
+    };
+    pager.wrap_lines = saved.wrap_lines;
+    pager.text_lines = saved.text_lines;
+    pager.prompt = saved.prompt;
+    pager.marks = saved.marks;
+    pager.focused_line = saved.focused_line;
+    pager.selection = saved.selection;
+    #[cfg(feature = "search")]
+    {
+        pager.search_term = saved.search_term;
+        pager.search_mark = saved.search_mark;
+        // Lines may have been added to the output, rewrapped or filtered in the
+        // meantime, so the matches are found again instead of being put aside
+        if pager.search_term.is_some() {
+            crate::search::set_match_indices(pager);
+        }
+    }
+    let rows_in_line = pager
+        .shown_rows(saved.line.saturating_sub(1))
+        .map_or(1, Vec::len);
+    pager.upper_mark =
+        pager.row_of_line(saved.line) + saved.row_in_line.min(rows_in_line.saturating_sub(1));
+    true
+}
+
+// Generate the text of the help screen from the bindings of the input handler
+pub(crate) fn help_text(pager: &Pager) -> String {
+    let bindings = pager.input_classifier.binding_descriptions();
+    let mut text = String::from("Keybindings\n\n");
+    if bindings.is_empty() {
+        text.push_str("The input handler does not describe it's keybindings\n");
+        return text;
+    }
+    // Align the descriptions, unless some keys are unreasonably long
+    let width = bindings
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(24);
+    for (keys, description) in bindings {
+        // Writing to a string never fails
+        let _ = writeln!(text, "  {keys:<width$}  {description}");
+    }
+    #[cfg(feature = "search")]
+    if !pager.highlights.is_empty() {
+        // Each pattern is shown in the style it is highlighted with
+        text.push_str("\nHighlights\n\n");
+        let width = pager
+            .highlights
+            .iter()
+            .map(|r| r.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(24);
+        for rule in &pager.highlights {
+            let pattern = crate::highlight::styled(rule.pattern.as_str(), rule.style);
+            let _ = writeln!(text, "  {:<width$}  {pattern}", rule.name);
+        }
+    }
+    text
+}
@@ -34,6 +34,23 @@ pub struct RegexError(
     #[from] regex::Error,
 );
 
+/// Errors that can occur while building a [`Keymap`](crate::input::Keymap)
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::module_name_repetitions)]
+pub enum KeymapError {
+    #[error("Invalid key specification: {0}")]
+    InvalidKey(String),
+
+    #[error("Unknown action: {0}")]
+    UnknownAction(String),
+
+    #[error("Invalid binding at line {line}: {text}")]
+    Syntax { line: usize, text: String },
+
+    #[error("Failed to read the keymap file")]
+    Io(#[from] std::io::Error),
+}
+
 /// Errors that can occur during setup
 #[derive(Debug, thiserror::Error)]
 #[allow(clippy::module_name_repetitions)]
@@ -0,0 +1,72 @@
+// Showing only the lines that match a pattern, like `&` in less
+//
+// The filter does not change the output. It keeps the indices of the logical
+// lines that are shown, which `Pager::shown_lines` uses in place of all the lines.
+// Lines added later are checked as they come in, so that filtering a growing
+// output only looks at each line once.
+
+use crate::utils::ansi::Stripped;
+use regex::Regex;
+
+pub(crate) struct Filter {
+    pattern: Regex,
+    // Show the lines that do not match instead
+    inverted: bool,
+    // Number of lines shown before and after each matching line
+    context: usize,
+    // Indices of the logical lines shown, in increasing order
+    shown: Vec<usize>,
+    // Number of lines already checked
+    scanned: usize,
+    // Index of the last line that matched
+    last_match: Option<usize>,
+}
+
+impl Filter {
+    pub(crate) fn new(pattern: Regex, inverted: bool, context: usize) -> Self {
+        Self {
+            pattern,
+            inverted,
+            context,
+            shown: Vec::new(),
+            scanned: 0,
+            last_match: None,
+        }
+    }
+
+    // Indices of the logical lines shown, in increasing order
+    pub(crate) fn shown(&self) -> &[usize] {
+        &self.shown
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        self.pattern.is_match(Stripped::new(line).text()) != self.inverted
+    }
+
+    // Check the lines of `lines` that were added since the last update
+    //
+    // `lines` is the text of the logical lines, as it was given
+    pub(crate) fn update(&mut self, lines: &[String]) {
+        for (idx, line) in lines.iter().enumerate().skip(self.scanned) {
+            if self.is_match(line) {
+                // Show the lines before it that are not shown yet
+                let from = idx
+                    .saturating_sub(self.context)
+                    .max(self.shown.last().map_or(0, |l| l + 1));
+                self.shown.extend(from..=idx);
+                self.last_match = Some(idx);
+            } else if self.last_match.is_some_and(|m| idx - m <= self.context) {
+                self.shown.push(idx);
+            }
+        }
+        self.scanned = lines.len();
+    }
+
+    // Check all of `lines` again, for when the output was replaced
+    pub(crate) fn reset(&mut self, lines: &[String]) {
+        self.shown.clear();
+        self.scanned = 0;
+        self.last_match = None;
+        self.update(lines);
+    }
+}
@@ -0,0 +1,167 @@
+// Fuzzy matching of search queries
+//
+// A fuzzy query matches text that contains all of it's characters in order, with
+// anything in between, like fzf. Whitespace in the query is left out. Each
+// match is scored by how close together the characters are and whether they
+// start words, so that the best matches can be visited first.
+
+use std::ops::Range;
+
+// Points for each character matched, along with the bonuses and penalties on top
+const SCORE_MATCH: i32 = 16;
+// The character starts a word
+const BONUS_BOUNDARY: i32 = 8;
+// The character is an uppercase letter after a lowercase one, like in camelCase
+const BONUS_CAMEL: i32 = 7;
+// The character comes right after the previous one matched
+const BONUS_CONSECUTIVE: i32 = 8;
+// Characters between two characters matched
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Fuzzy {
+    // The characters to find, in lowercase if the case is ignored
+    chars: Vec<char>,
+    ignore_case: bool,
+}
+
+// The characters of `query` that are looked for
+pub(crate) fn query_chars(query: &str) -> impl Iterator<Item = char> + '_ {
+    query.chars().filter(|c| !c.is_whitespace())
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// The bonus for matching `c` when it comes after `prev`
+fn bonus(prev: Option<char>, c: char) -> i32 {
+    match prev {
+        None => BONUS_BOUNDARY,
+        Some(prev) if !prev.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(prev) if prev.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        Some(_) => 0,
+    }
+}
+
+impl Fuzzy {
+    pub(crate) fn new(query: &str, ignore_case: bool) -> Self {
+        Self {
+            chars: query_chars(query)
+                .map(|c| if ignore_case { fold(c) } else { c })
+                .collect(),
+            ignore_case,
+        }
+    }
+
+    fn matches(&self, c: char, query: char) -> bool {
+        if self.ignore_case {
+            fold(c) == query
+        } else {
+            c == query
+        }
+    }
+
+    // Find a good match of the query in `text`
+    //
+    // Returns it's score and the byte ranges of the characters matched, with
+    // adjacent characters in the same range, or `None` if `text` does not
+    // contain the characters of the query in order.
+    pub(crate) fn find(&self, text: &str) -> Option<(i32, Vec<Range<usize>>)> {
+        // The end of the earliest match, then the latest start of a match ending
+        // there, which makes for a short match
+        let mut query = self.chars.iter().peekable();
+        query.peek()?;
+        let mut end = None;
+        for (idx, c) in text.char_indices() {
+            if query.next_if(|q| self.matches(c, **q)).is_some() && query.peek().is_none() {
+                end = Some(idx + c.len_utf8());
+                break;
+            }
+        }
+        let end = end?;
+        let mut query = self.chars.iter().rev().peekable();
+        let mut start = 0;
+        for (idx, c) in text[..end].char_indices().rev() {
+            if query.next_if(|q| self.matches(c, **q)).is_some() && query.peek().is_none() {
+                start = idx;
+                break;
+            }
+        }
+
+        let mut query = self.chars.iter().peekable();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut score = 0;
+        let mut prev = text[..start].chars().next_back();
+        // Number of characters since the last one matched
+        let mut gap = 0;
+        for (idx, c) in text[start..end].char_indices() {
+            let idx = start + idx;
+            if query.next_if(|q| self.matches(c, **q)).is_some() {
+                score += SCORE_MATCH + bonus(prev, c);
+                match ranges.last_mut() {
+                    Some(last) if gap == 0 => {
+                        score += BONUS_CONSECUTIVE;
+                        last.end = idx + c.len_utf8();
+                    }
+                    last => {
+                        if last.is_some() {
+                            score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (gap - 1);
+                        }
+                        ranges.push(idx..idx + c.len_utf8());
+                    }
+                }
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+            prev = Some(c);
+        }
+        Some((score, ranges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fuzzy;
+
+    fn score(query: &str, text: &str) -> i32 {
+        Fuzzy::new(query, true).find(text).unwrap().0
+    }
+
+    #[test]
+    fn test_fuzzy_find() {
+        let fuzzy = Fuzzy::new("fzf", false);
+        assert_eq!(
+            fuzzy.find("the fuzzy finder").unwrap().1,
+            [4..5, 6..7, 10..11]
+        );
+        assert!(fuzzy.find("find fuzz").is_none());
+        assert!(Fuzzy::new("", false).find("text").is_none());
+
+        // The match is kept short, and adjacent characters are in one range
+        assert_eq!(Fuzzy::new("ab", false).find("a ab").unwrap().1, vec![2..4]);
+        // Whitespace in the query is left out
+        assert_eq!(
+            Fuzzy::new("a b", false).find("a-b").unwrap().1,
+            [0..1, 2..3]
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_case() {
+        assert!(Fuzzy::new("ABC", false).find("abc").is_none());
+        assert!(Fuzzy::new("ABC", true).find("xaxbxc").is_some());
+        assert_eq!(Fuzzy::new("é", true).find("CAFÉ").unwrap().1, vec![3..5]);
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        // Characters together score best, then characters starting words
+        assert!(score("abc", "abc") > score("abc", "a_b_c"));
+        assert!(score("abc", "a_b_c") > score("abc", "axxbxxc"));
+        assert!(score("abc", "axxbxxc") > score("abc", "axxxxxbxxxxxc"));
+        assert!(score("fb", "fooBar") > score("fb", "foobar"));
+    }
+}
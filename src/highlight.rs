@@ -0,0 +1,498 @@
+// Highlighting the matches of the search and of the highlight rules
+//
+// Highlight rules are named patterns that stay highlighted with their own style,
+// whatever the search is. They are added by the application with
+// `Pager::add_highlight` or by the user with `+`.
+//
+// All the matches in a logical line are collected first and styled in a single
+// pass, so that the escape sequences added for one pattern are never matched by
+// another. Escape sequences already in the text are not matched either. Where
+// matches overlap, the search match wins, then the rule added first. The current
+// search match has it's own style, so that it stands out from the other matches.
+
+use crate::{
+    search::SearchTerm,
+    utils::ansi::{self, Stripped},
+};
+use crossterm::style::{Attribute, Color, ContentStyle, SetBackgroundColor, SetForegroundColor};
+use regex::Regex;
+use std::{convert::TryFrom, fmt::Write, ops::Range};
+
+pub(crate) struct HighlightRule {
+    pub(crate) name: String,
+    pub(crate) pattern: Regex,
+    pub(crate) style: ContentStyle,
+}
+
+// What the search highlights in a logical line
+#[derive(Clone)]
+pub(crate) struct SearchHighlight<'a> {
+    pub(crate) term: &'a SearchTerm,
+    // Byte range of the current match in the text of the line without it's
+    // escape sequences, if the current match is on the line
+    pub(crate) current: Option<Range<usize>>,
+    pub(crate) current_style: ContentStyle,
+}
+
+impl<'a> From<&'a SearchTerm> for SearchHighlight<'a> {
+    fn from(term: &'a SearchTerm) -> Self {
+        Self {
+            term,
+            current: None,
+            current_style: current_match_style(),
+        }
+    }
+}
+
+// The style of the current search match unless the application sets another,
+// black on yellow
+pub(crate) fn current_match_style() -> ContentStyle {
+    let mut style = ContentStyle::new();
+    style.foreground_color = Some(Color::Black);
+    style.background_color = Some(Color::Yellow);
+    style
+}
+
+// Parse a style written as words joined with `+`, like `bold+red+on_white`
+//
+// The words are the attributes `bold`, `dim`, `italic`, `underline` and
+// `reverse`, a color name for the text, and `on_` followed by a color name for
+// the background. Color names are those known to crossterm, like `red` or
+// `dark_cyan`.
+pub(crate) fn parse_style(spec: &str) -> Option<ContentStyle> {
+    let mut style = ContentStyle::new();
+    for word in spec.split('+') {
+        match word.to_lowercase().as_str() {
+            "bold" => style.attributes.set(Attribute::Bold),
+            "dim" => style.attributes.set(Attribute::Dim),
+            "italic" => style.attributes.set(Attribute::Italic),
+            "underline" => style.attributes.set(Attribute::Underlined),
+            "reverse" => style.attributes.set(Attribute::Reverse),
+            word => match word.strip_prefix("on_") {
+                Some(color) => style.background_color = Some(Color::try_from(color).ok()?),
+                None => style.foreground_color = Some(Color::try_from(word).ok()?),
+            },
+        }
+    }
+    Some(style)
+}
+
+// The escape sequences that turn `style` on
+fn style_codes(style: ContentStyle) -> String {
+    let mut codes = String::new();
+    // Writing to a string never fails
+    if let Some(bg) = style.background_color {
+        let _ = write!(codes, "{}", SetBackgroundColor(bg));
+    }
+    if let Some(fg) = style.foreground_color {
+        let _ = write!(codes, "{}", SetForegroundColor(fg));
+    }
+    for attribute in Attribute::iterator().filter(|a| style.attributes.has(*a)) {
+        let _ = write!(codes, "{attribute}");
+    }
+    codes
+}
+
+// Apply `style` to `text`, resetting every attribute after it
+pub(crate) fn styled(text: &str, style: ContentStyle) -> String {
+    format!("{}{text}{}", style_codes(style), Attribute::Reset)
+}
+
+// How a span of text is highlighted
+#[derive(Clone, Copy)]
+enum Highlight {
+    // A search match, shown in reverse video
+    Search,
+    // The current search match, shown in it's style
+    Current(ContentStyle),
+    // A match of a highlight rule, shown in it's style
+    Rule(ContentStyle),
+}
+
+// Find the spans of `text` to highlight, in the order they appear
+fn find_spans(
+    text: &str,
+    search: Option<&SearchHighlight>,
+    rules: &[HighlightRule],
+) -> Vec<(Range<usize>, Highlight)> {
+    let mut spans: Vec<(Range<usize>, Highlight)> = Vec::new();
+    let mut add = |range: Range<usize>, highlight: Highlight| {
+        let overlaps = spans
+            .iter()
+            .any(|(r, _)| r.start < range.end && range.start < r.end);
+        // Empty matches have nothing to highlight
+        if !range.is_empty() && !overlaps {
+            spans.push((range, highlight));
+        }
+    };
+    if let Some(search) = search {
+        for range in search.term.highlight_ranges(text) {
+            // The characters matched by a fuzzy search are parts of the match
+            let current = search
+                .current
+                .as_ref()
+                .is_some_and(|c| c.start <= range.start && range.end <= c.end);
+            if current {
+                add(range, Highlight::Current(search.current_style));
+            } else {
+                add(range, Highlight::Search);
+            }
+        }
+    }
+    for rule in rules {
+        for m in rule.pattern.find_iter(text) {
+            add(m.range(), Highlight::Rule(rule.style));
+        }
+    }
+    spans.sort_by_key(|(r, _)| r.start);
+    spans
+}
+
+// Highlight the matches of `search` and of `rules` in the rows of a logical line,
+// `text` being the text of the line as it was given
+//
+// Matching is done on the whole line without it's escape sequences, so that
+// matches running over the end of a row or over a change of colour are found.
+// The styles of the text are kept: they are turned on again when a highlight
+// ends, and the highlight is turned on again after any escape sequence inside it.
+pub(crate) fn highlight_rows(
+    rows: &mut [String],
+    text: &str,
+    search: Option<&SearchHighlight>,
+    rules: &[HighlightRule],
+) {
+    let stripped = Stripped::new(text);
+    let spans: Vec<(Range<usize>, Highlight)> = find_spans(stripped.text(), search, rules)
+        .into_iter()
+        .map(|(range, highlight)| (stripped.original_range(range), highlight))
+        .collect();
+    if spans.is_empty() {
+        return;
+    }
+    // The styles set by the text so far, since it last reset them
+    let mut text_style = String::new();
+    let starts = row_starts(text, rows);
+    for (row, start) in rows.iter_mut().zip(starts) {
+        let end = start + row.len();
+        let mut result = String::with_capacity(row.len());
+        let mut done = start;
+        // The parts of the spans that are on this row
+        for (range, highlight) in &spans {
+            let (from, to) = (range.start.max(start), range.end.min(end));
+            if from >= to {
+                continue;
+            }
+            let on = match highlight {
+                Highlight::Search => Attribute::Reverse.to_string(),
+                Highlight::Current(style) | Highlight::Rule(style) => style_codes(*style),
+            };
+            push_text(&mut result, &text[done..from], &mut text_style, "");
+            result.push_str(&on);
+            push_text(&mut result, &text[from..to], &mut text_style, &on);
+            let off = match highlight {
+                Highlight::Search => Attribute::NoReverse,
+                Highlight::Current(_) | Highlight::Rule(_) => Attribute::Reset,
+            };
+            let _ = write!(result, "{off}{text_style}");
+            done = to;
+        }
+        push_text(&mut result, &text[done..end], &mut text_style, "");
+        *row = result;
+    }
+}
+
+// Add `text` to `result`, keeping track of the styles it sets in `text_style`
+// and adding `highlight` again after each of it's escape sequences
+fn push_text(result: &mut String, text: &str, text_style: &mut String, highlight: &str) {
+    for (is_escape, part) in ansi::parts(text) {
+        result.push_str(part);
+        if is_escape {
+            if ansi::is_reset(part) {
+                text_style.clear();
+            } else if ansi::is_sgr(part) {
+                text_style.push_str(part);
+            }
+            result.push_str(highlight);
+        }
+    }
+}
+
+// Byte offset of the start of each row in `text`, the text of the logical line
+// the rows were wrapped from
+//
+// Rows are parts of the text, in order. Wrapping drops the spaces between them
+// but keeps every other character, even where a word is split over two rows.
+pub(crate) fn row_starts(text: &str, rows: &[String]) -> Vec<usize> {
+    let mut end = 0;
+    rows.iter()
+        .map(|row| {
+            // A row other than the first never starts with a space, so it is
+            // found right after the spaces that end the previous one
+            let start = text[end..].find(row.as_str()).map_or(end, |at| end + at);
+            end = start + row.len();
+            start
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        current_match_style, highlight_rows, parse_style, row_starts, styled, HighlightRule,
+        SearchHighlight,
+    };
+    use crate::search::{SearchOptions, SearchTerm};
+    use crossterm::style::{Attribute, Color};
+    use regex::Regex;
+
+    fn rule(name: &str, pattern: &str, style: &str) -> HighlightRule {
+        HighlightRule {
+            name: name.to_string(),
+            pattern: Regex::new(pattern).unwrap(),
+            style: parse_style(style).unwrap(),
+        }
+    }
+
+    fn highlight_line(line: &mut String, search: Option<&SearchTerm>, rules: &[HighlightRule]) {
+        let mut rows = vec![line.clone()];
+        highlight_rows(
+            &mut rows,
+            line,
+            search.map(SearchHighlight::from).as_ref(),
+            rules,
+        );
+        *line = rows.remove(0);
+    }
+
+    #[test]
+    fn test_highlight_matches() {
+        let mut line = "Integer placerat tristique nisl. placerat non mollis, magna orci dolor, \
+            placerat at vulputate neque nulla lacinia eros."
+            .to_string();
+        let pat = SearchTerm::from(Regex::new(r"\W\w+t\W").unwrap());
+        let result = format!(
+            "Integer{inverse} placerat {noinverse}tristique nisl.\
+{inverse} placerat {noinverse}non mollis, magna orci dolor,\
+{inverse} placerat {noinverse}at vulputate neque nulla lacinia \
+eros.",
+            inverse = Attribute::Reverse,
+            noinverse = Attribute::NoReverse
+        );
+
+        highlight_line(&mut line, Some(&pat), &[]);
+        assert_eq!(line, result);
+    }
+
+    #[test]
+    fn test_highlight_each_match_text() {
+        // Every match keeps it's own text, not the text of the first match
+        let mut line = "id=12 id=345".to_string();
+        let pat = SearchTerm::from(Regex::new(r"\d+").unwrap());
+        highlight_line(&mut line, Some(&pat), &[]);
+        assert_eq!(
+            line,
+            format!(
+                "id={r}12{n} id={r}345{n}",
+                r = Attribute::Reverse,
+                n = Attribute::NoReverse
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_fuzzy() {
+        // Only the characters matched are highlighted
+        let options = SearchOptions {
+            fuzzy: true,
+            ..SearchOptions::default()
+        };
+        let search = SearchTerm::new("fzf", options).unwrap();
+        let mut line = "the fuzzy finder".to_string();
+        highlight_line(&mut line, Some(&search), &[]);
+        assert_eq!(
+            line,
+            format!(
+                "the {r}f{n}u{r}z{n}zy {r}f{n}inder",
+                r = Attribute::Reverse,
+                n = Attribute::NoReverse
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_current_match() {
+        let term = SearchTerm::from(Regex::new("ab").unwrap());
+        let search = SearchHighlight {
+            current: Some(4..6),
+            ..SearchHighlight::from(&term)
+        };
+        let mut rows = vec!["ab, ab".to_string()];
+        highlight_rows(&mut rows, "ab, ab", Some(&search), &[]);
+        assert_eq!(
+            rows[0],
+            format!(
+                "{}ab{}, {}",
+                Attribute::Reverse,
+                Attribute::NoReverse,
+                styled("ab", current_match_style())
+            )
+        );
+
+        // All the characters of the current fuzzy match get it's style
+        let options = SearchOptions {
+            fuzzy: true,
+            ..SearchOptions::default()
+        };
+        let term = SearchTerm::new("fzf", options).unwrap();
+        let search = SearchHighlight {
+            current: Some(4..11),
+            ..SearchHighlight::from(&term)
+        };
+        let mut rows = vec!["the fuzzy finder".to_string()];
+        highlight_rows(&mut rows, "the fuzzy finder", Some(&search), &[]);
+        let style = current_match_style();
+        assert_eq!(
+            rows[0],
+            format!(
+                "the {}u{}zy {}inder",
+                styled("f", style),
+                styled("z", style),
+                styled("f", style)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_style() {
+        let style = parse_style("bold+Red+on_dark_blue").unwrap();
+        assert_eq!(style.foreground_color, Some(Color::Red));
+        assert_eq!(style.background_color, Some(Color::DarkBlue));
+        assert!(style.attributes.has(Attribute::Bold));
+        assert!(!style.attributes.has(Attribute::Italic));
+
+        assert!(parse_style("yellow").is_some());
+        assert!(parse_style("purple").is_none());
+        assert!(parse_style("on_").is_none());
+        assert!(parse_style("bold+").is_none());
+    }
+
+    #[test]
+    fn test_highlight_rules() {
+        let rules = [
+            rule("error", "ERROR", "red"),
+            rule("request", r"req-\d+", "yellow"),
+        ];
+        let mut line = "ERROR in req-42".to_string();
+        highlight_line(&mut line, None, &rules);
+        assert_eq!(
+            line,
+            format!(
+                "{} in {}",
+                styled("ERROR", rules[0].style),
+                styled("req-42", rules[1].style)
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_overlap() {
+        // The search wins over the rules, and earlier rules over later ones
+        let rules = [rule("first", "bc", "red"), rule("second", "abcd", "cyan")];
+        let search = SearchTerm::from(Regex::new("d").unwrap());
+        let mut line = "abcd".to_string();
+        highlight_line(&mut line, Some(&search), &rules);
+        assert_eq!(
+            line,
+            format!(
+                "a{}{}d{}",
+                styled("bc", rules[0].style),
+                Attribute::Reverse,
+                Attribute::NoReverse
+            )
+        );
+
+        // Escape sequences added for one pattern are not matched by the others
+        let rules = [rule("number", r"\d+", "red"), rule("m", "m", "blue")];
+        let mut line = "7".to_string();
+        highlight_line(&mut line, None, &rules);
+        assert_eq!(line, styled("7", rules[0].style));
+    }
+
+    #[test]
+    fn test_highlight_across_rows() {
+        let text = "Some text over rows";
+        let mut rows = vec!["Some text".to_string(), "over rows".to_string()];
+        assert_eq!(row_starts(text, &rows), [0, 10]);
+        let search = SearchTerm::from(Regex::new("text over").unwrap());
+        let rules = [rule("row", "ro", "red")];
+        highlight_rows(&mut rows, text, Some(&(&search).into()), &rules);
+        assert_eq!(
+            rows,
+            [
+                format!("Some {}text{}", Attribute::Reverse, Attribute::NoReverse),
+                format!(
+                    "{}over{} {}ws",
+                    Attribute::Reverse,
+                    Attribute::NoReverse,
+                    styled("ro", rules[0].style)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_wrapped_text() {
+        // Matches are highlighted where they are in the text of the line, even
+        // when it is wrapped inside a word or at a run of spaces
+        let text = "abcdef  gh";
+        let mut rows = vec!["abc".to_string(), "def".to_string(), "gh".to_string()];
+        assert_eq!(row_starts(text, &rows), [0, 3, 8]);
+        let search = SearchTerm::from(Regex::new("cd|f  g").unwrap());
+        highlight_rows(&mut rows, text, Some(&(&search).into()), &[]);
+        assert_eq!(
+            rows,
+            [
+                format!(
+                    "ab{r}c{n}",
+                    r = Attribute::Reverse,
+                    n = Attribute::NoReverse
+                ),
+                format!(
+                    "{r}d{n}e{r}f{n}",
+                    r = Attribute::Reverse,
+                    n = Attribute::NoReverse
+                ),
+                format!("{r}g{n}h", r = Attribute::Reverse, n = Attribute::NoReverse),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_styled_text() {
+        // Matches are found across escape sequences, and the colours of the text
+        // come back after the highlight
+        let mut line = "\x1b[31merror\x1b[0m here".to_string();
+        let search = SearchTerm::from(Regex::new("error here").unwrap());
+        highlight_line(&mut line, Some(&search), &[]);
+        assert_eq!(
+            line,
+            format!(
+                "\x1b[31m{r}error\x1b[0m{r} here{n}",
+                r = Attribute::Reverse,
+                n = Attribute::NoReverse
+            )
+        );
+
+        let rules = [rule("error", "ERROR", "bold")];
+        let mut line = "ER\x1b[32mROR done".to_string();
+        highlight_line(&mut line, None, &rules);
+        assert_eq!(
+            line,
+            format!(
+                "{b}ER\x1b[32m{b}ROR{reset}\x1b[32m done",
+                b = Attribute::Bold,
+                reset = Attribute::Reset
+            )
+        );
+    }
+}
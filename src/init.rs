@@ -7,7 +7,7 @@ use crate::search::{self, SearchMode};
 
 use crate::{
     error::AlternateScreenPagingError,
-    input::InputEvent,
+    input::{InputEvent, PagerState},
     utils::{
         draw, ev_handler,
         term::{cleanup, setup},
@@ -38,12 +38,8 @@ pub(crate) async fn dynamic_paging(
     let mut out = io::stdout();
     let guard = p.lock().await;
     let run_no_overflow = guard.run_no_overflow;
-    setup(&out, true, run_no_overflow)?;
+    setup(&out, true, run_no_overflow, guard.mouse_capture)?;
     drop(guard);
-    // Search related variables
-    // A marker of which element of s_co we are currently at
-    #[cfg(feature = "search")]
-    let mut s_mark = 0;
     // Whether to redraw the console
     #[allow(unused_assignments)]
     let mut redraw = true;
@@ -58,7 +54,8 @@ pub(crate) async fn dynamic_paging(
         let line_count = guard.num_lines();
         let have_just_overflowed = (last_line_count < guard.rows) && (line_count >= guard.rows);
         if have_just_overflowed && run_no_overflow {
-            setup(&out, true, true)?;
+            // The application may have changed the mouse capture since the start
+            setup(&out, true, true, guard.mouse_capture)?;
         }
         if last_line_count != line_count && (line_count < guard.rows || have_just_overflowed)
             || guard.message.1
@@ -75,32 +72,32 @@ pub(crate) async fn dynamic_paging(
             return Ok(cleanup(out, &guard.exit_strategy, false)?);
         }
 
+        // Search a chunk of the lines left to search, the lock is released
+        // in between so that the application can keep sending data
+        #[cfg(feature = "search")]
+        if search::continue_indexing(&mut guard) {
+            draw(&mut out, &mut guard)?;
+        }
+        let timeout = poll_timeout(&guard);
+
         drop(guard);
         // Check for events
-        if event::poll(std::time::Duration::from_millis(10))
-            .map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?
-        {
+        if event::poll(timeout).map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))? {
             // Lock the value again
             let mut lock = p.lock().await;
 
+            let ev =
+                event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?;
+            #[cfg(feature = "search")]
+            if search::stop_indexing(&mut lock, &ev) {
+                draw(&mut out, &mut lock)?;
+                continue;
+            }
             // Get the events
-            let input = lock.input_classifier.classify_input(
-                event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?,
-                lock.upper_mark,
-                #[cfg(feature = "search")]
-                lock.search_mode,
-                lock.line_numbers,
-                lock.message.0.is_some(),
-                lock.rows,
-            );
-            ev_handler::handle_input(
-                &input,
-                &mut lock,
-                &mut out,
-                &mut redraw,
-                #[cfg(feature = "search")]
-                &mut s_mark,
-            )?;
+            let input = lock
+                .input_classifier
+                .classify_input(ev, &PagerState::from_pager(&lock));
+            ev_handler::handle_input(input.as_ref(), &mut lock, &mut out, &mut redraw)?;
             // If redraw is true, then redraw the screen
             if redraw {
                 draw(&mut out, &mut lock)?;
@@ -118,39 +115,35 @@ pub(crate) async fn dynamic_paging(
 #[cfg(feature = "static_output")]
 pub(crate) fn static_paging(mut pager: Pager) -> Result<(), AlternateScreenPagingError> {
     let mut out = io::stdout();
-    setup(&out, false, pager.run_no_overflow)?;
+    setup(&out, false, pager.run_no_overflow, pager.mouse_capture)?;
     #[allow(unused_assignments)]
     let mut redraw = true;
 
-    #[cfg(feature = "search")]
-    let mut s_mark: usize = 0;
-
     draw(&mut out, &mut pager)?;
 
     loop {
+        // Search a chunk of the lines left to search, if any
+        #[cfg(feature = "search")]
+        if search::continue_indexing(&mut pager) {
+            draw(&mut out, &mut pager)?;
+        }
         // Check for events
-        if event::poll(std::time::Duration::from_millis(10))
+        if event::poll(poll_timeout(&pager))
             .map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?
         {
+            let ev =
+                event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?;
+            #[cfg(feature = "search")]
+            if search::stop_indexing(&mut pager, &ev) {
+                draw(&mut out, &mut pager)?;
+                continue;
+            }
             // Get the event
-            let input = pager.input_classifier.classify_input(
-                event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?,
-                pager.upper_mark,
-                #[cfg(feature = "search")]
-                pager.search_mode,
-                pager.line_numbers,
-                pager.message.0.is_some(),
-                pager.rows,
-            );
+            let input = pager
+                .input_classifier
+                .classify_input(ev, &PagerState::from_pager(&pager));
             // Handle the event
-            ev_handler::handle_input(
-                &input,
-                &mut pager,
-                &mut out,
-                &mut redraw,
-                #[cfg(feature = "search")]
-                &mut s_mark,
-            )?;
+            ev_handler::handle_input(input.as_ref(), &mut pager, &mut out, &mut redraw)?;
 
             // If there is some input, or messages and redraw is true
             // Redraw the screen
@@ -160,3 +153,16 @@ pub(crate) fn static_paging(mut pager: Pager) -> Result<(), AlternateScreenPagin
         }
     }
 }
+
+// How long to wait for an event before going on with the event loop
+//
+// While there are lines left to search for matches, the loop goes on right away
+// to search the next chunk.
+#[cfg_attr(not(feature = "search"), allow(unused_variables))]
+fn poll_timeout(pager: &Pager) -> std::time::Duration {
+    #[cfg(feature = "search")]
+    if pager.search_indexing.is_some() {
+        return std::time::Duration::ZERO;
+    }
+    std::time::Duration::from_millis(10)
+}
@@ -0,0 +1,978 @@
+//! Declarative keybindings, see [`Keymap`].
+
+use std::{fmt, path::Path, str::FromStr, time::Duration};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+#[cfg(feature = "search")]
+use super::SearchScope;
+use super::{
+    classify_mouse, sequence_to_string, CopyTarget, InputClassifier, InputEvent,
+    KeySequenceMatcher, PagerState, SequenceMatch,
+};
+use crate::error::KeymapError;
+#[cfg(feature = "search")]
+use crate::search::{SearchMode, SearchOptions};
+
+/// Named actions that can be bound to keys inside a [`Keymap`]
+///
+/// Every action has a stable name, which is used to refer to it inside
+/// keymap configuration strings. See [`Action::name`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Scroll up by one line
+    ScrollUp,
+    /// Scroll down by one line
+    ScrollDown,
+    /// Scroll up by half a screen
+    HalfPageUp,
+    /// Scroll down by half a screen
+    HalfPageDown,
+    /// Scroll up by an entire screen
+    PageUp,
+    /// Scroll down by an entire screen
+    PageDown,
+    /// Go to the very top of the output
+    GoToTop,
+    /// Go to the very bottom of the output
+    GoToBottom,
+    /// Go to the percentage of the output given as count
+    GoToPercent,
+    /// Ask for a line number and go to that line
+    GoToLinePrompt,
+    /// Remember the current position under the character given as argument
+    SetMark,
+    /// Go to the position remembered under the character given as argument
+    GoToMark,
+    /// Clear the message at the prompt if there is one, otherwise scroll down
+    /// by one line
+    Confirm,
+    /// Toggle line numbers if they are not forced enabled/disabled
+    ToggleLineNumbers,
+    /// Turn mouse capture on or off
+    ToggleMouseCapture,
+    /// Show the help screen
+    Help,
+    /// Copy the line the user is at to the clipboard, or as many lines as the
+    /// count
+    CopyLine,
+    /// Copy the lines visible on the screen to the clipboard
+    CopyScreen,
+    /// Copy the lines selected with the mouse to the clipboard
+    CopySelection,
+    /// Quit the pager
+    Quit,
+    /// Start a forward search
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    SearchForward,
+    /// Start a backward search
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    SearchReverse,
+    /// Go to the next search match, relative to the search direction
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    NextMatch,
+    /// Go to the previous search match, relative to the search direction
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    PrevMatch,
+    /// Copy the text of the current search match to the clipboard
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    CopyMatch,
+    /// Ask for a pattern and only show the lines matching it, with as many
+    /// lines around them as the count
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    Filter,
+    /// Ask for a pattern to highlight with a style, or for a highlight to remove
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    Highlight,
+    /// List the lines matching the search in the occur view
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    Occur,
+    /// Cycle searches between case sensitive, case insensitive and smart case
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ToggleSearchCase,
+    /// Toggle matching search queries as plain text instead of regular expressions
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ToggleSearchLiteral,
+    /// Toggle matching only whole words in searches
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ToggleSearchWholeWord,
+    /// Toggle fuzzy matching in searches
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ToggleSearchFuzzy,
+    /// Only search the lines on the screen, or all the lines again if searches
+    /// are limited already
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ToggleSearchScope,
+    /// Only search the lines from the one marked with the character given as
+    /// argument to the line the user is at
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    ScopeSearchToMark,
+    /// Run the custom action registered under the given name with
+    /// [`Pager::add_custom_action`](crate::Pager::add_custom_action)
+    ///
+    /// In configurations, this is written as `custom:<name>`.
+    Custom(String),
+}
+
+impl Action {
+    /// All the actions that are available with the currently enabled features
+    ///
+    /// [`Action::Custom`] is not part of this list.
+    pub const ALL: &'static [Self] = &[
+        Self::ScrollUp,
+        Self::ScrollDown,
+        Self::HalfPageUp,
+        Self::HalfPageDown,
+        Self::PageUp,
+        Self::PageDown,
+        Self::GoToTop,
+        Self::GoToBottom,
+        Self::GoToPercent,
+        Self::GoToLinePrompt,
+        Self::SetMark,
+        Self::GoToMark,
+        Self::Confirm,
+        Self::ToggleLineNumbers,
+        Self::ToggleMouseCapture,
+        Self::Help,
+        Self::CopyLine,
+        Self::CopyScreen,
+        Self::CopySelection,
+        Self::Quit,
+        #[cfg(feature = "search")]
+        Self::SearchForward,
+        #[cfg(feature = "search")]
+        Self::SearchReverse,
+        #[cfg(feature = "search")]
+        Self::NextMatch,
+        #[cfg(feature = "search")]
+        Self::PrevMatch,
+        #[cfg(feature = "search")]
+        Self::CopyMatch,
+        #[cfg(feature = "search")]
+        Self::Filter,
+        #[cfg(feature = "search")]
+        Self::Highlight,
+        #[cfg(feature = "search")]
+        Self::Occur,
+        #[cfg(feature = "search")]
+        Self::ToggleSearchCase,
+        #[cfg(feature = "search")]
+        Self::ToggleSearchLiteral,
+        #[cfg(feature = "search")]
+        Self::ToggleSearchWholeWord,
+        #[cfg(feature = "search")]
+        Self::ToggleSearchFuzzy,
+        #[cfg(feature = "search")]
+        Self::ToggleSearchScope,
+        #[cfg(feature = "search")]
+        Self::ScopeSearchToMark,
+    ];
+
+    /// The name by which this action is referred to in keymap configurations
+    ///
+    /// For [`Action::Custom`], this is the name of the custom action without
+    /// the `custom:` prefix.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ScrollUp => "scroll_up",
+            Self::ScrollDown => "scroll_down",
+            Self::HalfPageUp => "half_page_up",
+            Self::HalfPageDown => "half_page_down",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::GoToTop => "go_to_top",
+            Self::GoToBottom => "go_to_bottom",
+            Self::GoToPercent => "go_to_percent",
+            Self::GoToLinePrompt => "go_to_line",
+            Self::SetMark => "set_mark",
+            Self::GoToMark => "go_to_mark",
+            Self::Confirm => "confirm",
+            Self::ToggleLineNumbers => "toggle_line_numbers",
+            Self::ToggleMouseCapture => "toggle_mouse_capture",
+            Self::Help => "help",
+            Self::CopyLine => "copy_line",
+            Self::CopyScreen => "copy_screen",
+            Self::CopySelection => "copy_selection",
+            Self::Quit => "quit",
+            #[cfg(feature = "search")]
+            Self::SearchForward => "search_forward",
+            #[cfg(feature = "search")]
+            Self::SearchReverse => "search_reverse",
+            #[cfg(feature = "search")]
+            Self::NextMatch => "next_match",
+            #[cfg(feature = "search")]
+            Self::PrevMatch => "prev_match",
+            #[cfg(feature = "search")]
+            Self::CopyMatch => "copy_match",
+            #[cfg(feature = "search")]
+            Self::Filter => "filter",
+            #[cfg(feature = "search")]
+            Self::Highlight => "highlight",
+            #[cfg(feature = "search")]
+            Self::Occur => "occur",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchCase => "toggle_search_case",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchLiteral => "toggle_search_literal",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchWholeWord => "toggle_search_whole_word",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchFuzzy => "toggle_search_fuzzy",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchScope => "toggle_search_scope",
+            #[cfg(feature = "search")]
+            Self::ScopeSearchToMark => "scope_search_to_mark",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// A short human readable description of the action
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::ScrollUp => "Scroll up by one line",
+            Self::ScrollDown => "Scroll down by one line",
+            Self::HalfPageUp => "Scroll up by half a screen",
+            Self::HalfPageDown => "Scroll down by half a screen",
+            Self::PageUp => "Scroll up by entire page",
+            Self::PageDown => "Scroll down by entire page",
+            Self::GoToTop => "Go to the very top of the output",
+            Self::GoToBottom => "Go to the very bottom of the output",
+            Self::GoToPercent => "Go to the percentage of the output given as count",
+            Self::GoToLinePrompt => "Ask for a line number and go to it",
+            Self::SetMark => "Mark the current position with a letter",
+            Self::GoToMark => "Go to the position marked with a letter",
+            Self::Confirm => "Scroll down by one line or clear prompt messages",
+            Self::ToggleLineNumbers => "Toggle line numbers if not forced enabled/disabled",
+            Self::ToggleMouseCapture => "Toggle mouse capture for native text selection",
+            Self::Help => "Show this help screen",
+            Self::CopyLine => "Copy the current line to the clipboard",
+            Self::CopyScreen => "Copy the visible lines to the clipboard",
+            Self::CopySelection => "Copy the lines selected with the mouse to the clipboard",
+            Self::Quit => "Quit the pager",
+            #[cfg(feature = "search")]
+            Self::SearchForward => "Start forward search",
+            #[cfg(feature = "search")]
+            Self::SearchReverse => "Start backward search",
+            #[cfg(feature = "search")]
+            Self::NextMatch => "Go to the next search match",
+            #[cfg(feature = "search")]
+            Self::PrevMatch => "Go to the previous search match",
+            #[cfg(feature = "search")]
+            Self::CopyMatch => "Copy the current search match to the clipboard",
+            #[cfg(feature = "search")]
+            Self::Filter => "Only show lines matching a pattern, or not matching !pattern",
+            #[cfg(feature = "search")]
+            Self::Highlight => "Highlight as: name style pattern, or remove with: -name",
+            #[cfg(feature = "search")]
+            Self::Occur => "List the lines matching the search, Enter goes to one",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchCase => "Cycle search case between sensitive, insensitive and smart",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchLiteral => "Toggle searching for plain text instead of regexes",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchWholeWord => "Toggle searching for whole words only",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchFuzzy => "Toggle fuzzy searching",
+            #[cfg(feature = "search")]
+            Self::ToggleSearchScope => "Only search the visible lines, or all lines again",
+            #[cfg(feature = "search")]
+            Self::ScopeSearchToMark => "Only search from the line marked with a letter to here",
+            Self::Custom(_) => "Run an action defined by the application",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Self::Custom(name) = self {
+            write!(f, "custom:{name}")
+        } else {
+            f.write_str(self.name())
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("custom:") {
+            Some(name) if !name.is_empty() => Ok(Self::Custom(name.to_string())),
+            Some(_) => Err(KeymapError::UnknownAction(s.to_string())),
+            None => Self::ALL
+                .iter()
+                .find(|a| a.name() == s)
+                .cloned()
+                .ok_or_else(|| KeymapError::UnknownAction(s.to_string())),
+        }
+    }
+}
+
+/// A set of keybindings mapping keys to [`Action`]s
+///
+/// Keys are written as short specifications:
+///
+/// * A single character stands for that key, for example `q`, `G` or `/`.
+/// * Special keys are written inside angle brackets, for example `<PageDown>`,
+///   `<Enter>`, `<Space>`, `<Esc>` or `<F1>`. Names are case insensitive.
+///   `<lt>`, `<eq>` and `<hash>` stand for `<`, `=` and `#`, which start
+///   special keys, separate keys from actions and start comments in
+///   configuration files.
+/// * Modifiers are written as prefixes: `C-` for Ctrl, `M-` (or `A-`) for Alt
+///   and `S-` for Shift. For example `C-d`, `M-v` or `S-<Tab>`.
+/// * Several keys make up a sequence which must be typed one after the other,
+///   like `gg` or `C-x C-c`. `<char>` matches any character, which is given to
+///   the action as it's argument, like in `m<char>`. See [`KeySequenceMatcher`]
+///   for how sequences are matched.
+///
+/// A [`Keymap::default`] contains the bindings of the
+/// [`DefaultInputHandler`](super::DefaultInputHandler). Bindings can be added,
+/// replaced or removed on top of it, either programmatically or from a
+/// configuration string or file.
+///
+/// Typing digits before a key gives it a count, which repeats motions and
+/// makes [`Action::GoToTop`] and [`Action::GoToBottom`] jump to the line with
+/// that number instead. Digits that are not bound to an action are always
+/// treated as counts.
+///
+/// Configuration files contain one binding per line in the form of
+/// `<key> = <action>`. A line of the form `unbind <key>` removes a binding.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// # Example
+/// ```
+/// use minus::{input::{Action, Keymap}, Pager};
+///
+/// let mut keymap = Keymap::default();
+/// keymap.bind("C-f", Action::PageDown).unwrap();
+/// keymap.load_str("Z = quit\nunbind q").unwrap();
+///
+/// assert_eq!(keymap.action("Z").unwrap(), Some(Action::Quit));
+/// assert_eq!(keymap.action("q").unwrap(), None);
+///
+/// let mut pager = Pager::new().unwrap();
+/// pager.set_keymap(keymap);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: KeySequenceMatcher<Action>,
+}
+
+impl Keymap {
+    /// Create a keymap without any bindings
+    ///
+    /// Use [`Keymap::default`] to start with the default bindings instead.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            bindings: KeySequenceMatcher::default(),
+        }
+    }
+
+    /// Set the time to wait for the next key of a sequence before the keys
+    /// typed so far are dropped
+    ///
+    /// This is one second by default.
+    pub fn set_sequence_timeout(&mut self, timeout: Duration) {
+        self.bindings.set_timeout(timeout);
+    }
+
+    /// Create a keymap with the default bindings, with the bindings from `config`
+    /// layered on top of it
+    ///
+    /// See [`Keymap`] for the format of `config`.
+    ///
+    /// # Errors
+    /// This function will return an error if `config` contains an invalid key,
+    /// an unknown action or a malformed line.
+    pub fn from_config(config: &str) -> Result<Self, KeymapError> {
+        let mut keymap = Self::default();
+        keymap.load_str(config)?;
+        Ok(keymap)
+    }
+
+    /// Bind `key` to `action`, replacing any previous binding of `key`
+    ///
+    /// Bindings that are sequences starting with `key`, or that `key` starts
+    /// with, are removed too.
+    ///
+    /// # Errors
+    /// This function will return an error if `key` is not a valid key
+    /// specification.
+    pub fn bind(&mut self, key: &str, action: Action) -> Result<(), KeymapError> {
+        self.bindings.bind(key, action)
+    }
+
+    /// Remove the binding for `key`, returning the action it was bound to
+    ///
+    /// # Errors
+    /// This function will return an error if `key` is not a valid key
+    /// specification.
+    pub fn unbind(&mut self, key: &str) -> Result<Option<Action>, KeymapError> {
+        self.bindings.unbind(key)
+    }
+
+    /// Get the action bound to `key`, if any
+    ///
+    /// # Errors
+    /// This function will return an error if `key` is not a valid key
+    /// specification.
+    pub fn action(&self, key: &str) -> Result<Option<Action>, KeymapError> {
+        Ok(self.bindings.get(key)?.cloned())
+    }
+
+    /// Get all the keys bound to `action`, sorted
+    #[must_use]
+    pub fn keys(&self, action: &Action) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .bindings
+            .bindings()
+            .filter(|(_, a)| *a == action)
+            .map(|(seq, _)| sequence_to_string(seq))
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// List all the bindings in this keymap as `(key, action)` pairs
+    ///
+    /// The list is sorted in the order of [`Action::ALL`] and then by key.
+    /// Custom actions come last, sorted by name.
+    #[must_use]
+    pub fn bindings(&self) -> Vec<(String, Action)> {
+        let mut custom: Vec<Action> = self
+            .bindings
+            .bindings()
+            .filter(|(_, a)| matches!(a, Action::Custom(_)))
+            .map(|(_, a)| a.clone())
+            .collect();
+        custom.sort_by(|a, b| a.name().cmp(b.name()));
+        custom.dedup();
+        Action::ALL
+            .iter()
+            .chain(&custom)
+            .flat_map(|a| self.keys(a).into_iter().map(move |k| (k, a.clone())))
+            .collect()
+    }
+
+    /// Layer the bindings from a configuration string on top of this keymap
+    ///
+    /// See [`Keymap`] for the format of `config`.
+    ///
+    /// # Errors
+    /// This function will return an error if `config` contains an invalid key,
+    /// an unknown action or a malformed line. Bindings on the lines before
+    /// the erroneous one are still applied.
+    pub fn load_str(&mut self, config: &str) -> Result<(), KeymapError> {
+        for (idx, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let syntax_error = || KeymapError::Syntax {
+                line: idx + 1,
+                text: line.to_string(),
+            };
+            if let Some((key, action)) = line.split_once('=') {
+                let (key, action) = (key.trim(), action.trim());
+                if key.is_empty() || action.is_empty() {
+                    return Err(syntax_error());
+                }
+                self.bind(key, action.parse()?)?;
+            } else if let Some(key) = line.strip_prefix("unbind ") {
+                self.unbind(key.trim())?;
+            } else {
+                return Err(syntax_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Layer the bindings from a configuration file on top of this keymap
+    ///
+    /// See [`Keymap::load_str`].
+    ///
+    /// # Errors
+    /// This function will return an error if the file cannot be read or it's
+    /// contents are not a valid configuration.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), KeymapError> {
+        self.load_str(&std::fs::read_to_string(path)?)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self::new();
+        for (key, action) in [
+            ("<Up>", Action::ScrollUp),
+            ("k", Action::ScrollUp),
+            ("<Down>", Action::ScrollDown),
+            ("j", Action::ScrollDown),
+            ("<Enter>", Action::Confirm),
+            ("u", Action::HalfPageUp),
+            ("C-u", Action::HalfPageUp),
+            ("d", Action::HalfPageDown),
+            ("C-d", Action::HalfPageDown),
+            ("g", Action::GoToTop),
+            ("G", Action::GoToBottom),
+            ("%", Action::GoToPercent),
+            (":", Action::GoToLinePrompt),
+            ("m<char>", Action::SetMark),
+            ("'<char>", Action::GoToMark),
+            ("<PageUp>", Action::PageUp),
+            ("<PageDown>", Action::PageDown),
+            ("<Space>", Action::PageDown),
+            ("C-l", Action::ToggleLineNumbers),
+            ("M-m", Action::ToggleMouseCapture),
+            ("h", Action::Help),
+            ("yy", Action::CopyLine),
+            ("yp", Action::CopyScreen),
+            ("ys", Action::CopySelection),
+            ("q", Action::Quit),
+            ("C-c", Action::Quit),
+            #[cfg(feature = "search")]
+            ("/", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("?", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("n", Action::NextMatch),
+            #[cfg(feature = "search")]
+            ("p", Action::PrevMatch),
+            #[cfg(feature = "search")]
+            ("yn", Action::CopyMatch),
+            #[cfg(feature = "search")]
+            ("&", Action::Filter),
+            #[cfg(feature = "search")]
+            ("+", Action::Highlight),
+            #[cfg(feature = "search")]
+            ("o", Action::Occur),
+            #[cfg(feature = "search")]
+            ("-i", Action::ToggleSearchCase),
+            #[cfg(feature = "search")]
+            ("-l", Action::ToggleSearchLiteral),
+            #[cfg(feature = "search")]
+            ("-w", Action::ToggleSearchWholeWord),
+            #[cfg(feature = "search")]
+            ("-f", Action::ToggleSearchFuzzy),
+            #[cfg(feature = "search")]
+            ("-s", Action::ToggleSearchScope),
+            #[cfg(feature = "search")]
+            ("S<char>", Action::ScopeSearchToMark),
+        ] {
+            keymap.bind(key, action).unwrap();
+        }
+        keymap
+    }
+}
+
+impl InputClassifier for Keymap {
+    fn classify_input(&self, ev: Event, ps: &PagerState) -> Option<InputEvent> {
+        match ev {
+            Event::Key(key) => match self.bindings.feed(key) {
+                SequenceMatch::Matched { action, argument } => {
+                    action_to_event(action, argument, ps)
+                }
+                SequenceMatch::Pending => None,
+                SequenceMatch::NoMatch => match key {
+                    KeyEvent {
+                        code: KeyCode::Char(c),
+                        modifiers: KeyModifiers::NONE,
+                    } if c.is_ascii_digit() => Some(InputEvent::Number(c)),
+                    _ => None,
+                },
+            },
+            Event::Mouse(ev) => classify_mouse(ev, ps),
+            Event::Resize(cols, rows) => {
+                Some(InputEvent::UpdateTermArea(cols as usize, rows as usize))
+            }
+        }
+    }
+
+    fn binding_descriptions(&self) -> Vec<(String, String)> {
+        let mut descriptions: Vec<(String, String)> = Vec::new();
+        let mut last_action = None;
+        for (key, action) in self.bindings() {
+            if last_action.as_ref() == Some(&action) {
+                if let Some((keys, _)) = descriptions.last_mut() {
+                    keys.push_str(", ");
+                    keys.push_str(&key);
+                }
+                continue;
+            }
+            let description = match &action {
+                Action::Custom(name) => format!("Run {name}"),
+                action => action.description().to_string(),
+            };
+            descriptions.push((key, description));
+            last_action = Some(action);
+        }
+        descriptions
+    }
+}
+
+// Turn an action into the event to be handled by the pager, given the current
+// state of the pager
+fn action_to_event(
+    action: Action,
+    argument: Option<KeyEvent>,
+    ps: &PagerState,
+) -> Option<InputEvent> {
+    let (upper_mark, rows, count) = (ps.upper_mark, ps.rows, ps.count);
+    let times = count.unwrap_or(1);
+    let half_screen = (rows / 2).saturating_mul(times);
+    let screen = rows.saturating_sub(1).saturating_mul(times);
+    let argument = match argument {
+        Some(KeyEvent {
+            code: KeyCode::Char(c),
+            ..
+        }) => Some(c),
+        _ => None,
+    };
+    Some(match action {
+        // In the occur view, lines are selected one by one
+        #[cfg(feature = "search")]
+        Action::ScrollUp if ps.occur => {
+            InputEvent::FocusLine(ps.cursor_line.saturating_sub(times).max(1))
+        }
+        #[cfg(feature = "search")]
+        Action::ScrollDown if ps.occur => {
+            InputEvent::FocusLine(ps.cursor_line.saturating_add(times))
+        }
+        Action::ScrollUp => InputEvent::UpdateUpperMark(upper_mark.saturating_sub(times)),
+        Action::ScrollDown => InputEvent::UpdateUpperMark(upper_mark.saturating_add(times)),
+        Action::HalfPageUp => InputEvent::UpdateUpperMark(upper_mark.saturating_sub(half_screen)),
+        Action::HalfPageDown => InputEvent::UpdateUpperMark(upper_mark.saturating_add(half_screen)),
+        Action::PageUp => InputEvent::UpdateUpperMark(upper_mark.saturating_sub(screen)),
+        Action::PageDown => InputEvent::UpdateUpperMark(upper_mark.saturating_add(screen)),
+        Action::GoToTop | Action::GoToBottom if count.is_some() => InputEvent::GoToLine(times),
+        Action::GoToTop => InputEvent::UpdateUpperMark(0),
+        Action::GoToBottom => InputEvent::UpdateUpperMark(usize::MAX),
+        Action::GoToPercent => InputEvent::GoToPercent(count.unwrap_or(0)),
+        Action::GoToLinePrompt => InputEvent::GoToLinePrompt,
+        Action::SetMark => InputEvent::SetMark(argument?),
+        Action::GoToMark => InputEvent::GoToMark(argument?),
+        Action::Confirm if ps.message => InputEvent::RestorePrompt,
+        #[cfg(feature = "search")]
+        Action::Confirm if ps.occur => InputEvent::GoToOccurrence,
+        Action::Confirm => InputEvent::UpdateUpperMark(upper_mark.saturating_add(times)),
+        Action::ToggleLineNumbers => InputEvent::UpdateLineNumber(!ps.line_numbers),
+        Action::ToggleMouseCapture => InputEvent::UpdateMouseCapture(!ps.mouse_capture),
+        Action::Help => InputEvent::Help,
+        Action::CopyLine => InputEvent::Copy(CopyTarget::Lines(times)),
+        Action::CopyScreen => InputEvent::Copy(CopyTarget::Screen),
+        Action::CopySelection => InputEvent::Copy(CopyTarget::Selection),
+        Action::Quit => InputEvent::Exit,
+        #[cfg(feature = "search")]
+        Action::SearchForward => InputEvent::Search(SearchMode::Forward),
+        #[cfg(feature = "search")]
+        Action::SearchReverse => InputEvent::Search(SearchMode::Reverse),
+        #[cfg(feature = "search")]
+        Action::NextMatch if ps.search_mode == SearchMode::Reverse => InputEvent::PrevMatch,
+        #[cfg(feature = "search")]
+        Action::NextMatch => InputEvent::NextMatch,
+        #[cfg(feature = "search")]
+        Action::PrevMatch if ps.search_mode == SearchMode::Reverse => InputEvent::NextMatch,
+        #[cfg(feature = "search")]
+        Action::PrevMatch => InputEvent::PrevMatch,
+        #[cfg(feature = "search")]
+        Action::CopyMatch => InputEvent::Copy(CopyTarget::Match),
+        #[cfg(feature = "search")]
+        Action::Filter => InputEvent::Filter(count),
+        #[cfg(feature = "search")]
+        Action::Highlight => InputEvent::HighlightPrompt,
+        #[cfg(feature = "search")]
+        Action::ToggleSearchScope => InputEvent::SetSearchScope(toggle_search_scope(ps)),
+        #[cfg(feature = "search")]
+        Action::ScopeSearchToMark => InputEvent::SetSearchScope(SearchScope::Mark(argument?)),
+        #[cfg(feature = "search")]
+        Action::Occur => InputEvent::Occur,
+        #[cfg(feature = "search")]
+        Action::ToggleSearchCase
+        | Action::ToggleSearchLiteral
+        | Action::ToggleSearchWholeWord
+        | Action::ToggleSearchFuzzy => {
+            InputEvent::UpdateSearchOptions(toggle_search_option(&action, ps.search_options))
+        }
+        Action::Custom(name) => InputEvent::Custom(name),
+    })
+}
+
+// The search options after toggling the option of `action`
+#[cfg(feature = "search")]
+pub(crate) fn toggle_search_option(action: &Action, mut options: SearchOptions) -> SearchOptions {
+    match action {
+        Action::ToggleSearchCase => options.case = options.case.next(),
+        Action::ToggleSearchLiteral => options.literal = !options.literal,
+        Action::ToggleSearchWholeWord => options.whole_word = !options.whole_word,
+        Action::ToggleSearchFuzzy => options.fuzzy = !options.fuzzy,
+        _ => {}
+    }
+    options
+}
+
+// The scope of searches after toggling between the lines on the screen and all
+// the lines
+#[cfg(feature = "search")]
+pub(crate) fn toggle_search_scope(ps: &PagerState) -> SearchScope {
+    if ps.search_scope.is_some() {
+        SearchScope::All
+    } else {
+        SearchScope::Screen
+    }
+}
+
+// Names of the special keys that are written inside angle brackets
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Esc", KeyCode::Esc),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("eq", KeyCode::Char('=')),
+    ("hash", KeyCode::Char('#')),
+];
+
+/// Parse a key specification like `C-d`, `G` or `<PageDown>` into a key event
+///
+/// See [`Keymap`] for the syntax of key specifications.
+///
+/// # Errors
+/// This function will return an error if `spec` is not a valid key specification.
+pub fn parse_key(spec: &str) -> Result<KeyEvent, KeymapError> {
+    let invalid = || KeymapError::InvalidKey(spec.to_string());
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+
+    // Peel off the modifier prefixes. A lone `C` or `M` is a key, not a modifier
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        modifiers |= match rest.as_bytes()[0] {
+            b'C' => KeyModifiers::CONTROL,
+            b'M' | b'A' => KeyModifiers::ALT,
+            b'S' => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        rest = &rest[2..];
+    }
+
+    let code = if let Some(name) = rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')) {
+        if let Some(n) = name.strip_prefix(|c| c == 'F' || c == 'f') {
+            n.parse::<u8>().ok().filter(|n| *n > 0).map(KeyCode::F)
+        } else {
+            KEY_NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, code)| *code)
+        }
+        .ok_or_else(invalid)?
+    } else {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => return Err(invalid()),
+        }
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// Turn a key event into a key specification that can be parsed by [`parse_key`]
+#[must_use]
+pub fn key_to_string(key: KeyEvent) -> String {
+    let mut spec = String::new();
+    let mut modifiers = key.modifiers;
+    // Uppercase characters already carry the Shift modifier in them
+    if matches!(key.code, KeyCode::Char(c) if c.is_uppercase()) {
+        modifiers.remove(KeyModifiers::SHIFT);
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        spec.push_str("C-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        spec.push_str("M-");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        spec.push_str("S-");
+    }
+    match key.code {
+        KeyCode::F(n) => {
+            spec.push_str("<F");
+            spec.push_str(&n.to_string());
+            spec.push('>');
+        }
+        KeyCode::Char(c) if !matches!(c, ' ' | '<' | '=' | '#') => spec.push(c),
+        code => {
+            let name = KEY_NAMES
+                .iter()
+                .find(|(_, c)| *c == code)
+                .map_or("Null", |(n, _)| n);
+            spec.push('<');
+            spec.push_str(name);
+            spec.push('>');
+        }
+    }
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_to_string, parse_key, Action, Keymap};
+    use crate::error::KeymapError;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            parse_key("C-d").unwrap(),
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_key("G").unwrap(),
+            KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_key("<PageDown>").unwrap(),
+            KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("M-<space>").unwrap(),
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::ALT)
+        );
+        assert_eq!(
+            parse_key("<F12>").unwrap(),
+            KeyEvent::new(KeyCode::F(12), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("C").unwrap(),
+            KeyEvent::new(KeyCode::Char('C'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("-").unwrap(),
+            KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+
+        for spec in &["", "ab", "<Nope>", "C-", "<F0>", "X-a"] {
+            assert!(matches!(parse_key(spec), Err(KeymapError::InvalidKey(_))));
+        }
+    }
+
+    #[test]
+    fn test_key_roundtrip() {
+        for spec in &[
+            "C-d",
+            "G",
+            "<PageDown>",
+            "M-v",
+            "<Space>",
+            "S-<Tab>",
+            "<F5>",
+            "/",
+            "<lt>",
+            "<eq>",
+            "C-<hash>",
+        ] {
+            assert_eq!(&key_to_string(parse_key(spec).unwrap()), spec);
+        }
+    }
+
+    #[test]
+    fn test_bind_unbind() {
+        let mut keymap = Keymap::new();
+        assert_eq!(keymap.action("C-f").unwrap(), None);
+        keymap.bind("C-f", Action::PageDown).unwrap();
+        keymap.bind("<Space>", Action::PageDown).unwrap();
+        assert_eq!(keymap.action("C-f").unwrap(), Some(Action::PageDown));
+        assert_eq!(keymap.keys(&Action::PageDown), vec!["<Space>", "C-f"]);
+        assert_eq!(keymap.unbind("C-f").unwrap(), Some(Action::PageDown));
+        assert_eq!(keymap.action("C-f").unwrap(), None);
+        assert_eq!(
+            keymap.bindings(),
+            vec![("<Space>".to_string(), Action::PageDown)]
+        );
+    }
+
+    #[test]
+    fn test_load_str() {
+        let keymap = Keymap::from_config(
+            "
+            # A comment
+            b = page_up
+            C-q = quit
+            o = custom:open
+
+            unbind q
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            keymap.action("o").unwrap(),
+            Some(Action::Custom("open".to_string()))
+        );
+        assert_eq!(keymap.action("b").unwrap(), Some(Action::PageUp));
+        assert_eq!(keymap.action("C-q").unwrap(), Some(Action::Quit));
+        assert_eq!(keymap.action("q").unwrap(), None);
+        // Defaults are kept
+        assert_eq!(keymap.action("j").unwrap(), Some(Action::ScrollDown));
+
+        assert!(matches!(
+            Keymap::from_config("j = jump"),
+            Err(KeymapError::UnknownAction(a)) if a == "jump"
+        ));
+        // `=` and `#` are bound through their names
+        let keymap = Keymap::from_config("<eq> = help\n<hash> = quit").unwrap();
+        assert_eq!(keymap.action("=").unwrap(), Some(Action::Help));
+        assert_eq!(keymap.keys(&Action::Help), vec!["<eq>", "h"]);
+        assert_eq!(keymap.action("#").unwrap(), Some(Action::Quit));
+        assert!(matches!(
+            Keymap::from_config("# = quit\n#comment"),
+            Ok(keymap) if keymap.action("#").unwrap().is_none()
+        ));
+        assert!(matches!(
+            Keymap::from_config("= = help"),
+            Err(KeymapError::Syntax { line: 1, .. })
+        ));
+
+        assert!(matches!(
+            Keymap::from_config("\nj scroll_down"),
+            Err(KeymapError::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_action_names() {
+        for action in Action::ALL {
+            assert_eq!(action.name().parse::<Action>().unwrap(), *action);
+        }
+        let custom = Action::Custom("open".to_string());
+        assert_eq!(custom.to_string(), "custom:open");
+        assert_eq!("custom:open".parse::<Action>().unwrap(), custom);
+        assert!("custom:".parse::<Action>().is_err());
+    }
+}
@@ -1,14 +1,27 @@
 //! Provides the [`InputHandler`] trait, which can be used
 //! to customize the default keybindings of minus
+//!
+//! For most customizations, a [`Keymap`] is easier to use as it does not
+//! require matching on [`crossterm`] events directly
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+mod keymap;
+mod presets;
+mod sequence;
+
+pub use keymap::{key_to_string, parse_key, Action, Keymap};
+pub use presets::KeymapPreset;
+pub use sequence::{
+    parse_sequence, sequence_to_string, KeyPattern, KeySequenceMatcher, SequenceMatch,
+};
+
+use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
 
 #[cfg(feature = "search")]
-use crate::search::SearchMode;
-use crate::LineNumbers;
+use crate::search::{SearchMode, SearchOptions};
+use crate::{LineNumbers, Pager};
 
 /// Events handled by the `minus` pager.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(clippy::module_name_repetitions)]
 pub enum InputEvent {
     /// `Ctrl+C` or `Q`, exits the application.
@@ -22,6 +35,21 @@ pub enum InputEvent {
     UpdateLineNumber(LineNumbers),
     /// Restore the original prompt
     RestorePrompt,
+    /// A digit was typed. Digits are accumulated into a count which is passed
+    /// to the next classified event, like `10j` in Vim.
+    Number(char),
+    /// `N G`, jump to the given logical line number, starting from 1.
+    GoToLine(usize),
+    /// `N %`, jump to the line at the given percentage of the output.
+    GoToPercent(usize),
+    /// `:`, ask the user for a line number and jump to it.
+    GoToLinePrompt,
+    /// `m` followed by a character, remember the current position under that
+    /// character.
+    SetMark(char),
+    /// `'` followed by a character, jump to the position remembered under that
+    /// character.
+    GoToMark(char),
     /// `/`, Searching for certain pattern of text
     #[cfg(feature = "search")]
     Search(SearchMode),
@@ -31,6 +59,236 @@ pub enum InputEvent {
     /// Get to the previous match in forward mode
     #[cfg(feature = "search")]
     PrevMatch,
+    /// `&`, ask for a pattern and only show the lines matching it, or the lines
+    /// not matching it if the pattern starts with `!`. An empty pattern shows
+    /// all the lines again. Contains the number of lines to show around each
+    /// matching line, if given as count.
+    #[cfg(feature = "search")]
+    Filter(Option<usize>),
+    /// `+`, ask for a highlight rule to add as `name style pattern`, or for the
+    /// name of one to remove as `-name`. See
+    /// [`Pager::add_highlight`](crate::Pager::add_highlight).
+    #[cfg(feature = "search")]
+    HighlightPrompt,
+    /// `o`, list the lines matching the search with their line numbers in a
+    /// temporary buffer, the occur view. Exiting from it returns to the output.
+    #[cfg(feature = "search")]
+    Occur,
+    /// `Enter` in the occur view, go back to the output at the line the user is
+    /// at in the list.
+    #[cfg(feature = "search")]
+    GoToOccurrence,
+    /// `-i`, `-l`, `-w` or `-f`, change how searches match the text for the rest of
+    /// the session. Contains the new options.
+    #[cfg(feature = "search")]
+    UpdateSearchOptions(SearchOptions),
+    /// `-s` or `S` followed by a character, only search some of the lines, see
+    /// [`SearchScope`].
+    #[cfg(feature = "search")]
+    SetSearchScope(SearchScope),
+    /// Turn mouse capture on or off. Contains the new value.
+    UpdateMouseCapture(bool),
+    /// The left mouse button was pressed, which focuses the line under it. Contains
+    /// the index of the wrapped row under the mouse.
+    MouseClick(usize),
+    /// The mouse was moved with the left button held, which selects the lines
+    /// between the click and this row. Contains the index of the wrapped row
+    /// under the mouse.
+    MouseDrag(usize),
+    /// The left mouse button was released, which copies the selected lines to the
+    /// clipboard. Contains the index of the wrapped row under the mouse.
+    MouseRelease(usize),
+    /// Focus the given logical line, starting from 1, and scroll to it if it is
+    /// not on the screen. `Up` and `Down` send this in the occur view.
+    FocusLine(usize),
+    /// `h`, show the help screen listing the keybindings. Exiting from the help
+    /// screen returns to the output.
+    Help,
+    /// Copy some text to the clipboard, see [`CopyTarget`].
+    Copy(CopyTarget),
+    /// Run the custom action registered under this name with
+    /// [`Pager::add_custom_action`](crate::Pager::add_custom_action). Nothing
+    /// happens if no action is registered under the name.
+    Custom(String),
+}
+
+/// What to copy to the clipboard with [`InputEvent::Copy`]
+///
+/// Text is copied with the OSC 52 escape sequence, which must be supported by
+/// the terminal. Text larger than the limit set with
+/// [`Pager::set_clipboard_limit`](crate::Pager::set_clipboard_limit) is not
+/// copied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CopyTarget {
+    /// This many lines, starting from the line the user is at
+    Lines(usize),
+    /// The lines visible on the screen
+    Screen,
+    /// The lines selected with the mouse
+    Selection,
+    /// The text of the current search match
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    Match,
+}
+
+/// Which lines to search with [`InputEvent::SetSearchScope`]
+///
+/// See [`Pager::set_search_scope`](crate::Pager::set_search_scope).
+#[cfg(feature = "search")]
+#[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SearchScope {
+    /// All the lines again
+    All,
+    /// The lines visible on the screen
+    Screen,
+    /// The lines from the one marked with the character to the line the user
+    /// is at
+    Mark(char),
+}
+
+/// What the pager should do after running a custom action
+///
+/// See [`Pager::add_custom_action`](crate::Pager::add_custom_action).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomActionResult {
+    /// Nothing needs to be done
+    None,
+    /// Redraw the screen, for example because the output was changed
+    Redraw,
+    /// Show a message at the prompt, see [`Pager::send_message`](crate::Pager::send_message)
+    Message(String),
+    /// Quit the pager, just like when the user presses `q`
+    Exit,
+}
+
+/// A read-only snapshot of the pager state, given to an [`InputClassifier`]
+///
+/// New fields may be added in the future without a major version bump, so
+/// this cannot be constructed with a struct literal outside of `minus`. Use
+/// [`PagerState::default`] and change the fields instead, for example when
+/// testing a custom classifier.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct PagerState {
+    /// The index of the first wrapped row displayed on the screen
+    pub upper_mark: usize,
+    /// Number of rows of the terminal, including the prompt
+    pub rows: usize,
+    /// Number of columns of the terminal
+    pub cols: usize,
+    /// Total number of wrapped rows of the output
+    pub line_count: usize,
+    /// Total number of lines of the output, as they were before wrapping
+    pub logical_line_count: usize,
+    /// The line the user is at, starting from 1
+    ///
+    /// This is the line focused by clicking on it, as long as it is on the
+    /// screen, or the line at the top of the screen otherwise.
+    pub cursor_line: usize,
+    /// Whether the application has finished sending data
+    pub end_stream: bool,
+    /// The current line number configuration
+    pub line_numbers: LineNumbers,
+    /// Whether a message is being displayed at the prompt
+    pub message: bool,
+    /// Whether mouse events are captured by the pager
+    pub mouse_capture: bool,
+    /// Number of rows to scroll for a step of the mouse wheel
+    pub wheel_step: usize,
+    /// The count typed before the current event, if any
+    ///
+    /// Classifiers should return [`InputEvent::Number`] for digits to let the
+    /// user type such counts.
+    pub count: Option<usize>,
+    /// The direction of the current search
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub search_mode: SearchMode,
+    /// The pattern of the current search, if any
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub search_term: Option<String>,
+    /// The options searches are matched with
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub search_options: SearchOptions,
+    /// Whether the occur view, listing the lines matching the search, is shown
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub occur: bool,
+    /// The first and last line searched, as numbered in the whole output, if
+    /// searches are limited to some of the lines
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub search_scope: Option<(usize, usize)>,
+}
+
+impl Default for PagerState {
+    fn default() -> Self {
+        Self {
+            upper_mark: 0,
+            rows: 1,
+            cols: 1,
+            line_count: 0,
+            logical_line_count: 0,
+            cursor_line: 1,
+            end_stream: false,
+            line_numbers: LineNumbers::Disabled,
+            message: false,
+            mouse_capture: false,
+            wheel_step: 5,
+            count: None,
+            #[cfg(feature = "search")]
+            search_mode: SearchMode::Unknown,
+            #[cfg(feature = "search")]
+            search_term: None,
+            #[cfg(feature = "search")]
+            search_options: SearchOptions::default(),
+            #[cfg(feature = "search")]
+            occur: false,
+            #[cfg(feature = "search")]
+            search_scope: None,
+        }
+    }
+}
+
+impl PagerState {
+    /// Take a snapshot of the state of `pager`
+    pub(crate) fn from_pager(pager: &Pager) -> Self {
+        Self {
+            upper_mark: pager.upper_mark,
+            rows: pager.rows,
+            cols: pager.cols,
+            line_count: pager.num_lines(),
+            logical_line_count: pager.num_logical_lines(),
+            cursor_line: pager.cursor_line(),
+            end_stream: pager.end_stream,
+            line_numbers: pager.line_numbers,
+            message: pager.message.0.is_some(),
+            mouse_capture: pager.mouse_capture,
+            wheel_step: pager.wheel_step,
+            count: pager.prefix_num.parse().ok(),
+            #[cfg(feature = "search")]
+            search_mode: pager.search_mode,
+            #[cfg(feature = "search")]
+            search_term: pager
+                .search_term
+                .as_ref()
+                .map(|term| term.regex.as_str().to_string()),
+            #[cfg(feature = "search")]
+            search_options: pager.search_options,
+            #[cfg(feature = "search")]
+            occur: pager
+                .saved_content
+                .as_ref()
+                .is_some_and(|saved| saved.occur.is_some()),
+            #[cfg(feature = "search")]
+            search_scope: pager.search_scope,
+        }
+    }
 }
 
 /// Define custom keybindings
@@ -45,33 +303,31 @@ pub enum InputEvent {
 ///
 /// # Example
 /// ```
-/// use minus::{input::{InputEvent, InputClassifier}, LineNumbers, Pager};
-#[cfg_attr(feature = "search", doc = "use minus::SearchMode;")]
+/// use minus::{input::{InputEvent, InputClassifier, PagerState}, Pager};
 /// use crossterm::event::{Event, KeyEvent, KeyCode, KeyModifiers};
 ///
 /// struct CustomInputHandler;
 /// impl InputClassifier for CustomInputHandler {
-///     fn classify_input(
-///         &self,
-///         ev: Event,
-///         upper_mark: usize,
-///         // This `search_mode` parameter is available, only if `search` feature is enabled
-#[cfg_attr(feature = "search", doc = "        search_mode: SearchMode,")]
-///         ln: LineNumbers,
-///         message: bool,
-///         rows: usize
-///     ) -> Option<InputEvent> {
-///             match ev {
-///                 Event::Key(KeyEvent {
-///                     code: KeyCode::Up,
-///                     modifiers: KeyModifiers::NONE,
-///                 })
-///                 | Event::Key(KeyEvent {
-///                     code: KeyCode::Char('j'),
-///                     modifiers: KeyModifiers::NONE,
-///                 }) => Some(InputEvent::UpdateUpperMark
-///                       (upper_mark.saturating_sub(1))),
-///                 _ => None
+///     fn classify_input(&self, ev: Event, ps: &PagerState) -> Option<InputEvent> {
+///         match ev {
+///             Event::Key(KeyEvent {
+///                 code: KeyCode::Up,
+///                 modifiers: KeyModifiers::NONE,
+///             })
+///             | Event::Key(KeyEvent {
+///                 code: KeyCode::Char('j'),
+///                 modifiers: KeyModifiers::NONE,
+///             }) => Some(InputEvent::UpdateUpperMark(
+///                 ps.upper_mark.saturating_sub(ps.count.unwrap_or(1)),
+///             )),
+///             // Jump to the last page
+///             Event::Key(KeyEvent {
+///                 code: KeyCode::End,
+///                 modifiers: KeyModifiers::NONE,
+///             }) => Some(InputEvent::UpdateUpperMark(
+///                 ps.line_count.saturating_sub(ps.rows - 1),
+///             )),
+///             _ => None,
 ///         }
 ///     }
 /// }
@@ -83,177 +339,64 @@ pub enum InputEvent {
 /// ```
 #[allow(clippy::module_name_repetitions)]
 pub trait InputClassifier {
-    fn classify_input(
-        &self,
-        ev: Event,
-        upper_mark: usize,
-        #[cfg(feature = "search")] search_mode: SearchMode,
-        ln: LineNumbers,
-        message: bool,
-        rows: usize,
-    ) -> Option<InputEvent>;
+    /// Classify the event `ev` given the current state `ps` of the pager
+    fn classify_input(&self, ev: Event, ps: &PagerState) -> Option<InputEvent>;
+
+    /// Describe the keybindings for the help screen, as pairs of the keys and
+    /// what they do
+    ///
+    /// Returns no descriptions by default, in which case the help screen says so.
+    fn binding_descriptions(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 }
 
 /// The default keybindings in `minus`. These can be overriden by
 /// making a custom input handler struct and implementing the [`InputHandler`] trait
-pub struct DefaultInputHandler;
+///
+/// The bindings are those of [`Keymap::default`], so the help screen always
+/// lists the keys that are handled.
+#[derive(Default)]
+pub struct DefaultInputHandler {
+    keymap: Keymap,
+}
 
 impl InputClassifier for DefaultInputHandler {
-    #[allow(clippy::too_many_lines)]
-    fn classify_input(
-        &self,
-        ev: Event,
-        upper_mark: usize,
-        #[cfg(feature = "search")] search_mode: SearchMode,
-        ln: LineNumbers,
-        message: bool,
-        rows: usize,
-    ) -> Option<InputEvent> {
-        #[allow(clippy::unnested_or_patterns)]
-        match ev {
-            // Scroll up by one.
-            Event::Key(KeyEvent {
-                code,
-                modifiers: KeyModifiers::NONE,
-            }) if code == KeyCode::Up || code == KeyCode::Char('k') => {
-                Some(InputEvent::UpdateUpperMark(upper_mark.saturating_sub(1)))
-            }
-
-            // Scroll down by one.
-            Event::Key(KeyEvent {
-                code,
-                modifiers: KeyModifiers::NONE,
-            }) if code == KeyCode::Down || code == KeyCode::Char('j') => {
-                Some(InputEvent::UpdateUpperMark(upper_mark.saturating_add(1)))
-            }
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                if message {
-                    Some(InputEvent::RestorePrompt)
-                } else {
-                    Some(InputEvent::UpdateUpperMark(upper_mark.saturating_add(1)))
-                }
-            }
-
-            // Scroll up by half screen height.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('u'),
-                modifiers,
-            }) if modifiers == KeyModifiers::CONTROL || modifiers == KeyModifiers::NONE => {
-                let half_screen = (rows / 2) as usize;
-                Some(InputEvent::UpdateUpperMark(
-                    upper_mark.saturating_sub(half_screen),
-                ))
-            }
-            // Scroll down by half screen height.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('d'),
-                modifiers,
-            }) if modifiers == KeyModifiers::CONTROL || modifiers == KeyModifiers::NONE => {
-                let half_screen = (rows / 2) as usize;
-                Some(InputEvent::UpdateUpperMark(
-                    upper_mark.saturating_add(half_screen),
-                ))
-            }
-
-            // Mouse scroll up/down
-            Event::Mouse(MouseEvent {
-                kind: MouseEventKind::ScrollUp,
-                ..
-            }) => Some(InputEvent::UpdateUpperMark(upper_mark.saturating_sub(5))),
-            Event::Mouse(MouseEvent {
-                kind: MouseEventKind::ScrollDown,
-                ..
-            }) => Some(InputEvent::UpdateUpperMark(upper_mark.saturating_add(5))),
-            // Go to top.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('g'),
-                modifiers: KeyModifiers::NONE,
-            }) => Some(InputEvent::UpdateUpperMark(0)),
-            // Go to bottom.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('g'),
-                modifiers: KeyModifiers::SHIFT,
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('G'),
-                modifiers: KeyModifiers::SHIFT,
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('G'),
-                modifiers: KeyModifiers::NONE,
-            }) => Some(InputEvent::UpdateUpperMark(usize::MAX)),
+    fn classify_input(&self, ev: Event, ps: &PagerState) -> Option<InputEvent> {
+        self.keymap.classify_input(ev, ps)
+    }
 
-            // Page Up/Down
-            Event::Key(KeyEvent {
-                code: KeyCode::PageUp,
-                modifiers: KeyModifiers::NONE,
-            }) => Some(InputEvent::UpdateUpperMark(
-                upper_mark.saturating_sub(rows - 1),
-            )),
-            Event::Key(KeyEvent {
-                code: c,
-                modifiers: KeyModifiers::NONE,
-            }) if c == KeyCode::PageDown || c == KeyCode::Char(' ') => Some(
-                InputEvent::UpdateUpperMark(upper_mark.saturating_add(rows - 1)),
-            ),
+    fn binding_descriptions(&self) -> Vec<(String, String)> {
+        self.keymap.binding_descriptions()
+    }
+}
 
-            // Resize event from the terminal.
-            Event::Resize(cols, rows) => {
-                Some(InputEvent::UpdateTermArea(cols as usize, rows as usize))
-            }
-            // Switch line number display.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('l'),
-                modifiers: KeyModifiers::CONTROL,
-            }) => Some(InputEvent::UpdateLineNumber(!ln)),
-            // Quit.
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::NONE,
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-            }) => Some(InputEvent::Exit),
-            #[cfg(feature = "search")]
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('/'),
-                modifiers: KeyModifiers::NONE,
-            }) => Some(InputEvent::Search(SearchMode::Forward)),
-            #[cfg(feature = "search")]
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('?'),
-                modifiers: KeyModifiers::NONE,
-            }) => Some(InputEvent::Search(SearchMode::Reverse)),
-            #[cfg(feature = "search")]
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('n'),
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                if search_mode == SearchMode::Reverse {
-                    Some(InputEvent::PrevMatch)
-                } else {
-                    Some(InputEvent::NextMatch)
-                }
-            }
-            #[cfg(feature = "search")]
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('p'),
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                if search_mode == SearchMode::Reverse {
-                    Some(InputEvent::NextMatch)
-                } else {
-                    Some(InputEvent::PrevMatch)
-                }
-            }
-            _ => None,
+// Classify mouse events the same way for every built-in classifier
+pub(crate) fn classify_mouse(ev: MouseEvent, ps: &PagerState) -> Option<InputEvent> {
+    // Row under the mouse, unless it is on the prompt line
+    let row = usize::from(ev.row);
+    let row = if row < ps.rows.saturating_sub(1) {
+        Some(ps.upper_mark.saturating_add(row))
+    } else {
+        None
+    };
+    match ev.kind {
+        MouseEventKind::ScrollUp => Some(InputEvent::UpdateUpperMark(
+            ps.upper_mark.saturating_sub(ps.wheel_step),
+        )),
+        MouseEventKind::ScrollDown => Some(InputEvent::UpdateUpperMark(
+            ps.upper_mark.saturating_add(ps.wheel_step),
+        )),
+        MouseEventKind::Down(MouseButton::Left) => row.map(InputEvent::MouseClick),
+        MouseEventKind::Drag(MouseButton::Left) => row.map(InputEvent::MouseDrag),
+        MouseEventKind::Up(MouseButton::Left) => {
+            Some(InputEvent::MouseRelease(row.unwrap_or_else(|| {
+                ps.upper_mark.saturating_add(ps.rows.saturating_sub(2))
+            })))
         }
+        _ => None,
     }
 }
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,456 @@
+//! Keymaps that reproduce the navigation of other well known pagers and editors
+
+use super::{Action, Keymap};
+
+/// A built-in set of keybindings, see [`Pager::set_keymap_preset`](crate::Pager::set_keymap_preset)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeymapPreset {
+    /// The default keybindings of minus, see [`Keymap::default`]
+    Default,
+    /// Keybindings of `less`, see [`Keymap::less`]
+    Less,
+    /// Keybindings of Vim in read-only mode, see [`Keymap::vim`]
+    Vim,
+    /// Keybindings of Emacs' `view-mode`, see [`Keymap::emacs`]
+    Emacs,
+}
+
+impl Keymap {
+    /// Create the keymap of the given preset
+    #[must_use]
+    pub fn preset(preset: KeymapPreset) -> Self {
+        match preset {
+            KeymapPreset::Default => Self::default(),
+            KeymapPreset::Less => Self::less(),
+            KeymapPreset::Vim => Self::vim(),
+            KeymapPreset::Emacs => Self::emacs(),
+        }
+    }
+
+    /// Keybindings that reproduce the navigation of `less`
+    ///
+    /// * `j`, `e`, `C-e`, `C-n`, `<Down>` and `<Enter>` scroll down by one line
+    /// * `k`, `y`, `C-y`, `C-k`, `C-p` and `<Up>` scroll up by one line
+    /// * `f`, `z`, `C-f`, `C-v`, `<Space>` and `<PageDown>` scroll down by a screen
+    /// * `b`, `w`, `C-b`, `M-v` and `<PageUp>` scroll up by a screen
+    /// * `d` and `C-d` scroll down by half a screen, `u` and `C-u` up
+    /// * `g`, `<lt>`, `M-<lt>` and `<Home>` go to the top, `G`, `>`, `M->` and `<End>`
+    ///   to the bottom. With a count, they go to that line instead
+    /// * `p` and `%` go to the percentage given as count
+    /// * `/` and `?` search forward and backward, `n` and `N` go to the next
+    ///   match in the same and the opposite direction
+    /// * `m<char>` marks the position, `'<char>` goes back to it
+    /// * `-N` toggles line numbers, `-i` cycles the case mode of searches
+    /// * `&` only shows the lines matching a pattern
+    /// * `h` and `H` show the help screen
+    /// * `q`, `Q`, `:q`, `:Q`, `ZZ` and `C-c` quit
+    #[must_use]
+    pub fn less() -> Self {
+        with_bindings([
+            ("j", Action::ScrollDown),
+            ("e", Action::ScrollDown),
+            ("C-e", Action::ScrollDown),
+            ("C-n", Action::ScrollDown),
+            ("<Down>", Action::ScrollDown),
+            ("<Enter>", Action::Confirm),
+            ("k", Action::ScrollUp),
+            ("y", Action::ScrollUp),
+            ("C-y", Action::ScrollUp),
+            ("C-k", Action::ScrollUp),
+            ("C-p", Action::ScrollUp),
+            ("<Up>", Action::ScrollUp),
+            ("f", Action::PageDown),
+            ("z", Action::PageDown),
+            ("C-f", Action::PageDown),
+            ("C-v", Action::PageDown),
+            ("<Space>", Action::PageDown),
+            ("<PageDown>", Action::PageDown),
+            ("b", Action::PageUp),
+            ("w", Action::PageUp),
+            ("C-b", Action::PageUp),
+            ("M-v", Action::PageUp),
+            ("<PageUp>", Action::PageUp),
+            ("d", Action::HalfPageDown),
+            ("C-d", Action::HalfPageDown),
+            ("u", Action::HalfPageUp),
+            ("C-u", Action::HalfPageUp),
+            ("g", Action::GoToTop),
+            ("<lt>", Action::GoToTop),
+            ("M-<lt>", Action::GoToTop),
+            ("<Home>", Action::GoToTop),
+            ("G", Action::GoToBottom),
+            (">", Action::GoToBottom),
+            ("M->", Action::GoToBottom),
+            ("<End>", Action::GoToBottom),
+            ("p", Action::GoToPercent),
+            ("%", Action::GoToPercent),
+            ("m<char>", Action::SetMark),
+            ("'<char>", Action::GoToMark),
+            ("-N", Action::ToggleLineNumbers),
+            ("M-m", Action::ToggleMouseCapture),
+            ("h", Action::Help),
+            ("H", Action::Help),
+            ("q", Action::Quit),
+            ("Q", Action::Quit),
+            (":q", Action::Quit),
+            (":Q", Action::Quit),
+            ("ZZ", Action::Quit),
+            ("C-c", Action::Quit),
+            #[cfg(feature = "search")]
+            ("/", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("?", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("n", Action::NextMatch),
+            #[cfg(feature = "search")]
+            ("N", Action::PrevMatch),
+            #[cfg(feature = "search")]
+            ("-i", Action::ToggleSearchCase),
+            #[cfg(feature = "search")]
+            ("&", Action::Filter),
+        ])
+    }
+
+    /// Keybindings that reproduce the navigation of Vim in read-only mode
+    ///
+    /// * `j`, `C-e`, `C-n`, `<Down>` and `<Enter>` scroll down by one line
+    /// * `k`, `C-y`, `C-p` and `<Up>` scroll up by one line
+    /// * `C-f` and `<PageDown>` scroll down by a screen, `C-b` and `<PageUp>` up
+    /// * `C-d` scrolls down by half a screen, `C-u` up
+    /// * `gg` and `<Home>` go to the top, `G` and `<End>` to the bottom. With a
+    ///   count, they go to that line instead
+    /// * `%` goes to the percentage given as count, `:` asks for a line number
+    /// * `/` and `?` search forward and backward, `n` and `N` go to the next
+    ///   match in the same and the opposite direction
+    /// * `m<char>` marks the position, `'<char>` and `` `<char> `` go back to it
+    /// * `yy` copies the current line, `yp` the screen, `ys` the mouse selection
+    ///   and `yn` the current search match
+    /// * `<F1>` shows the help screen
+    /// * `q`, `ZZ`, `ZQ` and `C-c` quit
+    #[must_use]
+    pub fn vim() -> Self {
+        with_bindings([
+            ("j", Action::ScrollDown),
+            ("C-e", Action::ScrollDown),
+            ("C-n", Action::ScrollDown),
+            ("<Down>", Action::ScrollDown),
+            ("<Enter>", Action::Confirm),
+            ("k", Action::ScrollUp),
+            ("C-y", Action::ScrollUp),
+            ("C-p", Action::ScrollUp),
+            ("<Up>", Action::ScrollUp),
+            ("C-f", Action::PageDown),
+            ("<PageDown>", Action::PageDown),
+            ("C-b", Action::PageUp),
+            ("<PageUp>", Action::PageUp),
+            ("C-d", Action::HalfPageDown),
+            ("C-u", Action::HalfPageUp),
+            ("gg", Action::GoToTop),
+            ("<Home>", Action::GoToTop),
+            ("G", Action::GoToBottom),
+            ("<End>", Action::GoToBottom),
+            ("%", Action::GoToPercent),
+            (":", Action::GoToLinePrompt),
+            ("m<char>", Action::SetMark),
+            ("'<char>", Action::GoToMark),
+            ("`<char>", Action::GoToMark),
+            ("yy", Action::CopyLine),
+            ("yp", Action::CopyScreen),
+            ("ys", Action::CopySelection),
+            ("M-m", Action::ToggleMouseCapture),
+            ("<F1>", Action::Help),
+            ("q", Action::Quit),
+            ("ZZ", Action::Quit),
+            ("ZQ", Action::Quit),
+            ("C-c", Action::Quit),
+            #[cfg(feature = "search")]
+            ("/", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("?", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("n", Action::NextMatch),
+            #[cfg(feature = "search")]
+            ("N", Action::PrevMatch),
+            #[cfg(feature = "search")]
+            ("yn", Action::CopyMatch),
+        ])
+    }
+
+    /// Keybindings that reproduce the navigation of Emacs' `view-mode`
+    ///
+    /// * `C-n`, `<Down>` and `<Enter>` scroll down by one line
+    /// * `y`, `C-p` and `<Up>` scroll up by one line
+    /// * `<Space>`, `C-v` and `<PageDown>` scroll down by a screen
+    /// * `<Backspace>`, `M-v` and `<PageUp>` scroll up by a screen
+    /// * `d` scrolls down by half a screen, `u` up
+    /// * `<lt>`, `M-<lt>` and `<Home>` go to the top, `>`, `M->` and `<End>` to
+    ///   the bottom. With a count, they go to that line instead
+    /// * `g` and `M-g g` ask for a line number, `%` goes to the percentage given
+    ///   as count
+    /// * `s`, `C-s` and `/` search forward, `r`, `C-r` and `\` backward, `n` and
+    ///   `p` go to the next and previous match. `M-c` cycles the case mode of
+    ///   searches and `M-r` toggles searching for plain text
+    /// * `M-w` copies the mouse selection
+    /// * `h` and `?` show the help screen
+    /// * `q`, `Q` and `C-x C-c` quit
+    #[must_use]
+    pub fn emacs() -> Self {
+        with_bindings([
+            ("C-n", Action::ScrollDown),
+            ("<Down>", Action::ScrollDown),
+            ("<Enter>", Action::Confirm),
+            ("y", Action::ScrollUp),
+            ("C-p", Action::ScrollUp),
+            ("<Up>", Action::ScrollUp),
+            ("<Space>", Action::PageDown),
+            ("C-v", Action::PageDown),
+            ("<PageDown>", Action::PageDown),
+            ("<Backspace>", Action::PageUp),
+            ("M-v", Action::PageUp),
+            ("<PageUp>", Action::PageUp),
+            ("d", Action::HalfPageDown),
+            ("u", Action::HalfPageUp),
+            ("<lt>", Action::GoToTop),
+            ("M-<lt>", Action::GoToTop),
+            ("<Home>", Action::GoToTop),
+            (">", Action::GoToBottom),
+            ("M->", Action::GoToBottom),
+            ("<End>", Action::GoToBottom),
+            ("g", Action::GoToLinePrompt),
+            ("M-g g", Action::GoToLinePrompt),
+            ("%", Action::GoToPercent),
+            ("M-w", Action::CopySelection),
+            ("M-m", Action::ToggleMouseCapture),
+            ("h", Action::Help),
+            ("?", Action::Help),
+            ("q", Action::Quit),
+            ("Q", Action::Quit),
+            ("C-x C-c", Action::Quit),
+            #[cfg(feature = "search")]
+            ("s", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("C-s", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("/", Action::SearchForward),
+            #[cfg(feature = "search")]
+            ("r", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("C-r", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("\\", Action::SearchReverse),
+            #[cfg(feature = "search")]
+            ("n", Action::NextMatch),
+            #[cfg(feature = "search")]
+            ("p", Action::PrevMatch),
+            #[cfg(feature = "search")]
+            ("M-c", Action::ToggleSearchCase),
+            #[cfg(feature = "search")]
+            ("M-r", Action::ToggleSearchLiteral),
+        ])
+    }
+}
+
+// Create a keymap with only the given bindings
+fn with_bindings(bindings: impl IntoIterator<Item = (&'static str, Action)>) -> Keymap {
+    let mut keymap = Keymap::new();
+    for (key, action) in bindings {
+        keymap.bind(key, action).expect("valid preset binding");
+    }
+    keymap
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{
+        parse_sequence, InputClassifier, InputEvent, KeyPattern, Keymap, KeymapPreset, PagerState,
+    };
+    #[cfg(feature = "search")]
+    use crate::{SearchCase, SearchMode, SearchOptions};
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+    // Feed the keys of `spec` one after the other and return what the last one
+    // was classified as
+    fn classify(keymap: &Keymap, spec: &str, ps: &PagerState) -> Option<InputEvent> {
+        let mut result = None;
+        for key in parse_sequence(spec).unwrap() {
+            let key = match key {
+                KeyPattern::Key(key) => key,
+                KeyPattern::AnyChar => KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+            };
+            result = keymap.classify_input(Event::Key(key), ps);
+        }
+        result
+    }
+
+    fn state() -> PagerState {
+        PagerState {
+            upper_mark: 20,
+            rows: 11,
+            #[cfg(feature = "search")]
+            search_mode: SearchMode::Forward,
+            ..PagerState::default()
+        }
+    }
+
+    // Checks that all `keys` are classified as `expected`
+    fn check(keymap: &Keymap, keys: &[&str], expected: &InputEvent) {
+        for key in keys {
+            assert_eq!(
+                classify(keymap, key, &state()).as_ref(),
+                Some(expected),
+                "{key}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_less() {
+        let keymap = Keymap::preset(KeymapPreset::Less);
+        let down = InputEvent::UpdateUpperMark(21);
+        check(
+            &keymap,
+            &["j", "e", "C-e", "C-n", "<Down>", "<Enter>"],
+            &down,
+        );
+        let up = InputEvent::UpdateUpperMark(19);
+        check(&keymap, &["k", "y", "C-y", "C-k", "C-p", "<Up>"], &up);
+        let page_down = InputEvent::UpdateUpperMark(30);
+        check(
+            &keymap,
+            &["f", "z", "C-f", "C-v", "<Space>", "<PageDown>"],
+            &page_down,
+        );
+        let page_up = InputEvent::UpdateUpperMark(10);
+        check(&keymap, &["b", "w", "C-b", "M-v", "<PageUp>"], &page_up);
+        check(&keymap, &["d", "C-d"], &InputEvent::UpdateUpperMark(25));
+        check(&keymap, &["u", "C-u"], &InputEvent::UpdateUpperMark(15));
+        let top = InputEvent::UpdateUpperMark(0);
+        check(&keymap, &["g", "<lt>", "M-<lt>", "<Home>"], &top);
+        let bottom = InputEvent::UpdateUpperMark(usize::MAX);
+        check(&keymap, &["G", ">", "M->", "<End>"], &bottom);
+        check(&keymap, &["p", "%"], &InputEvent::GoToPercent(0));
+        check(&keymap, &["ma"], &InputEvent::SetMark('a'));
+        check(&keymap, &["'a"], &InputEvent::GoToMark('a'));
+        let quit = InputEvent::Exit;
+        check(&keymap, &["q", "Q", ":q", ":Q", "ZZ", "C-c"], &quit);
+        check(&keymap, &["h", "H"], &InputEvent::Help);
+        #[cfg(feature = "search")]
+        {
+            check(&keymap, &["n"], &InputEvent::NextMatch);
+            check(&keymap, &["N"], &InputEvent::PrevMatch);
+            let options = SearchOptions {
+                case: SearchCase::Insensitive,
+                ..SearchOptions::default()
+            };
+            check(&keymap, &["-i"], &InputEvent::UpdateSearchOptions(options));
+        }
+
+        let ps = PagerState {
+            count: Some(5),
+            ..state()
+        };
+        assert_eq!(classify(&keymap, "G", &ps), Some(InputEvent::GoToLine(5)));
+        assert_eq!(
+            classify(&keymap, "-N", &ps),
+            Some(InputEvent::UpdateLineNumber(crate::LineNumbers::Enabled))
+        );
+    }
+
+    #[test]
+    fn test_vim() {
+        let keymap = Keymap::preset(KeymapPreset::Vim);
+        let down = InputEvent::UpdateUpperMark(21);
+        check(&keymap, &["j", "C-e", "C-n", "<Down>", "<Enter>"], &down);
+        let up = InputEvent::UpdateUpperMark(19);
+        check(&keymap, &["k", "C-y", "C-p", "<Up>"], &up);
+        check(
+            &keymap,
+            &["C-f", "<PageDown>"],
+            &InputEvent::UpdateUpperMark(30),
+        );
+        check(
+            &keymap,
+            &["C-b", "<PageUp>"],
+            &InputEvent::UpdateUpperMark(10),
+        );
+        check(&keymap, &["C-d"], &InputEvent::UpdateUpperMark(25));
+        check(&keymap, &["C-u"], &InputEvent::UpdateUpperMark(15));
+        check(&keymap, &["gg", "<Home>"], &InputEvent::UpdateUpperMark(0));
+        let bottom = InputEvent::UpdateUpperMark(usize::MAX);
+        check(&keymap, &["G", "<End>"], &bottom);
+        check(&keymap, &[":"], &InputEvent::GoToLinePrompt);
+        check(&keymap, &["'a", "`a"], &InputEvent::GoToMark('a'));
+        check(&keymap, &["q", "ZZ", "ZQ", "C-c"], &InputEvent::Exit);
+        check(&keymap, &["<F1>"], &InputEvent::Help);
+        // A single `g` waits for the next key
+        assert_eq!(classify(&keymap, "g", &state()), None);
+        assert_eq!(
+            classify(&keymap, "j", &state()),
+            Some(InputEvent::UpdateUpperMark(21))
+        );
+        #[cfg(feature = "search")]
+        {
+            check(&keymap, &["n"], &InputEvent::NextMatch);
+            check(&keymap, &["N"], &InputEvent::PrevMatch);
+        }
+
+        let ps = PagerState {
+            count: Some(7),
+            ..state()
+        };
+        assert_eq!(classify(&keymap, "gg", &ps), Some(InputEvent::GoToLine(7)));
+        assert_eq!(
+            classify(&keymap, "%", &ps),
+            Some(InputEvent::GoToPercent(7))
+        );
+    }
+
+    #[test]
+    fn test_emacs() {
+        let keymap = Keymap::preset(KeymapPreset::Emacs);
+        let down = InputEvent::UpdateUpperMark(21);
+        check(&keymap, &["C-n", "<Down>", "<Enter>"], &down);
+        check(
+            &keymap,
+            &["y", "C-p", "<Up>"],
+            &InputEvent::UpdateUpperMark(19),
+        );
+        let page_down = InputEvent::UpdateUpperMark(30);
+        check(&keymap, &["<Space>", "C-v", "<PageDown>"], &page_down);
+        let page_up = InputEvent::UpdateUpperMark(10);
+        check(&keymap, &["<Backspace>", "M-v", "<PageUp>"], &page_up);
+        check(&keymap, &["d"], &InputEvent::UpdateUpperMark(25));
+        check(&keymap, &["u"], &InputEvent::UpdateUpperMark(15));
+        let top = InputEvent::UpdateUpperMark(0);
+        check(&keymap, &["<lt>", "M-<lt>", "<Home>"], &top);
+        let bottom = InputEvent::UpdateUpperMark(usize::MAX);
+        check(&keymap, &[">", "M->", "<End>"], &bottom);
+        check(&keymap, &["g", "M-g g"], &InputEvent::GoToLinePrompt);
+        check(&keymap, &["q", "Q", "C-x C-c"], &InputEvent::Exit);
+        check(&keymap, &["h", "?"], &InputEvent::Help);
+        // `C-c` on it's own does nothing, like in Emacs
+        assert_eq!(classify(&keymap, "C-c", &state()), None);
+        #[cfg(feature = "search")]
+        {
+            let forward = InputEvent::Search(SearchMode::Forward);
+            check(&keymap, &["s", "C-s", "/"], &forward);
+            let reverse = InputEvent::Search(SearchMode::Reverse);
+            check(&keymap, &["r", "C-r", "\\"], &reverse);
+            check(&keymap, &["n"], &InputEvent::NextMatch);
+            check(&keymap, &["p"], &InputEvent::PrevMatch);
+            let options = SearchOptions {
+                literal: true,
+                ..SearchOptions::default()
+            };
+            check(&keymap, &["M-r"], &InputEvent::UpdateSearchOptions(options));
+        }
+    }
+
+    #[test]
+    fn test_default_preset() {
+        assert_eq!(
+            Keymap::preset(KeymapPreset::Default).bindings(),
+            Keymap::default().bindings()
+        );
+    }
+}
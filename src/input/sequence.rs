@@ -0,0 +1,517 @@
+//! Matching multi-key sequences like `gg` or `m<char>`, see [`KeySequenceMatcher`].
+
+use std::{
+    fmt,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::{key_to_string, parse_key};
+use crate::error::KeymapError;
+
+/// One element of a key sequence
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum KeyPattern {
+    /// Matches exactly this key
+    Key(KeyEvent),
+    /// Matches any character typed without Ctrl or Alt, which is handed
+    /// over as the argument of the match. Written as `<char>`.
+    AnyChar,
+}
+
+impl KeyPattern {
+    fn matches(self, key: KeyEvent) -> bool {
+        match self {
+            Self::Key(k) => normalize(k) == normalize(key),
+            Self::AnyChar => is_plain_char(key),
+        }
+    }
+
+    // Whether some key can be matched by both `self` and `other`
+    fn overlaps(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::Key(a), Self::Key(b)) => normalize(a) == normalize(b),
+            (Self::AnyChar, Self::Key(k)) | (Self::Key(k), Self::AnyChar) => is_plain_char(k),
+            (Self::AnyChar, Self::AnyChar) => true,
+        }
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(k) => f.write_str(&key_to_string(*k)),
+            Self::AnyChar => f.write_str("<char>"),
+        }
+    }
+}
+
+// Terminals often report symbols like `?` along with the Shift modifier that
+// was needed to type them. Drop it so that `?` matches regardless.
+fn normalize(mut key: KeyEvent) -> KeyEvent {
+    if matches!(key.code, KeyCode::Char(c) if !c.is_alphabetic()) {
+        key.modifiers.remove(KeyModifiers::SHIFT);
+    }
+    key
+}
+
+fn is_plain_char(key: KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char(_))
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+/// Parse a sequence of key specifications like `gg`, `C-x C-c` or `m<char>`
+///
+/// Each key is written like in [`parse_key`]. Keys can optionally be separated
+/// by spaces. `<char>` stands for any character, see [`KeyPattern::AnyChar`].
+///
+/// # Errors
+/// This function will return an error if `spec` is empty or contains an invalid
+/// key specification.
+pub fn parse_sequence(spec: &str) -> Result<Vec<KeyPattern>, KeymapError> {
+    let mut sequence = Vec::new();
+    let mut rest = spec.trim_start();
+    while !rest.is_empty() {
+        // Skip over the modifier prefixes, a lone `C` or `M` is a key though
+        let mut end = 0;
+        while rest.len() > end + 2
+            && matches!(rest.as_bytes()[end], b'C' | b'M' | b'A' | b'S')
+            && rest.as_bytes()[end + 1] == b'-'
+        {
+            end += 2;
+        }
+        // Then comes either a bracketed key name or a single character
+        let key = &rest[end..];
+        end += match key.find('>') {
+            Some(close) if key.starts_with('<') => close + 1,
+            _ => key.chars().next().map_or(0, char::len_utf8),
+        };
+        let (token, remainder) = rest.split_at(end);
+        if token.eq_ignore_ascii_case("<char>") {
+            sequence.push(KeyPattern::AnyChar);
+        } else {
+            sequence.push(KeyPattern::Key(
+                parse_key(token).map_err(|_| KeymapError::InvalidKey(spec.to_string()))?,
+            ));
+        }
+        rest = remainder.trim_start();
+    }
+    if sequence.is_empty() {
+        return Err(KeymapError::InvalidKey(spec.to_string()));
+    }
+    Ok(sequence)
+}
+
+/// Turn a key sequence into a specification that can be parsed by [`parse_sequence`]
+#[must_use]
+pub fn sequence_to_string(sequence: &[KeyPattern]) -> String {
+    let keys: Vec<String> = sequence.iter().map(ToString::to_string).collect();
+    // Sequences of plain characters are easier to read without separators
+    if sequence.iter().all(|p| match p {
+        KeyPattern::Key(k) => key_to_string(*k).chars().count() == 1,
+        KeyPattern::AnyChar => true,
+    }) {
+        keys.concat()
+    } else {
+        keys.join(" ")
+    }
+}
+
+/// The result of feeding a key to a [`KeySequenceMatcher`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceMatch<T> {
+    /// A complete sequence was typed
+    Matched {
+        /// The value bound to the sequence
+        action: T,
+        /// The key matched by [`KeyPattern::AnyChar`], if the sequence has one
+        argument: Option<KeyEvent>,
+    },
+    /// The keys typed so far are the beginning of at least one sequence. The
+    /// matcher waits for more keys.
+    Pending,
+    /// The key does not start or continue any sequence
+    NoMatch,
+}
+
+#[derive(Debug, Default)]
+struct PendingKeys {
+    keys: Vec<KeyEvent>,
+    last: Option<Instant>,
+}
+
+/// Matches multi-key sequences like `gg`, `ZZ` or `m<char>` as keys are
+/// typed one at a time
+///
+/// Keys are fed to the matcher with [`KeySequenceMatcher::feed`]. While the
+/// keys typed so far are the beginning of a bound sequence, the matcher buffers
+/// them and answers [`SequenceMatch::Pending`]. Buffered keys are dropped if
+/// the next key does not arrive within the timeout.
+///
+/// Matching is deterministic because a sequence can never be the beginning of
+/// another one: binding a sequence removes all the bindings that start with it
+/// and all the bindings it starts with. If a key matches both an exact key and
+/// a `<char>` pattern, the exact key wins. If a key does not continue the
+/// buffered keys, the buffer is dropped and the key is matched on it's own.
+///
+/// This is used by [`Keymap`](super::Keymap) and the
+/// [`DefaultInputHandler`](super::DefaultInputHandler), but can be used by any
+/// custom [`InputClassifier`](super::InputClassifier) too.
+///
+/// # Example
+/// ```
+/// use minus::input::{KeySequenceMatcher, SequenceMatch};
+/// use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+/// use std::time::Duration;
+///
+/// let mut matcher = KeySequenceMatcher::new(Duration::from_secs(1));
+/// matcher.bind("gg", "top").unwrap();
+/// matcher.bind("m<char>", "mark").unwrap();
+///
+/// let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+/// assert_eq!(matcher.feed(key('g')), SequenceMatch::Pending);
+/// assert_eq!(
+///     matcher.feed(key('g')),
+///     SequenceMatch::Matched { action: "top", argument: None }
+/// );
+/// matcher.feed(key('m'));
+/// assert_eq!(
+///     matcher.feed(key('a')),
+///     SequenceMatch::Matched { action: "mark", argument: Some(key('a')) }
+/// );
+/// ```
+#[derive(Debug)]
+pub struct KeySequenceMatcher<T> {
+    bindings: Vec<(Vec<KeyPattern>, T)>,
+    timeout: Duration,
+    pending: Mutex<PendingKeys>,
+}
+
+impl<T: Clone> KeySequenceMatcher<T> {
+    /// The default time to wait for the next key of a sequence
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Create a matcher without any bindings which waits for at most `timeout`
+    /// between two keys of a sequence
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            bindings: Vec::new(),
+            timeout,
+            pending: Mutex::new(PendingKeys::default()),
+        }
+    }
+
+    /// Set the time to wait for the next key of a sequence
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Bind a sequence specification to `action`
+    ///
+    /// See [`parse_sequence`] for the format of `spec`.
+    ///
+    /// # Errors
+    /// This function will return an error if `spec` is not a valid sequence.
+    pub fn bind(&mut self, spec: &str, action: T) -> Result<(), KeymapError> {
+        self.bind_sequence(parse_sequence(spec)?, action);
+        Ok(())
+    }
+
+    /// Bind an already parsed sequence to `action`
+    ///
+    /// Any binding that is the same as `sequence`, starts with it or that it
+    /// starts with is removed.
+    pub fn bind_sequence(&mut self, sequence: Vec<KeyPattern>, action: T) {
+        self.bindings.retain(|(bound, _)| {
+            let shorter = bound.len().min(sequence.len());
+            // Sequences of the same length that only overlap through `<char>`
+            // can coexist, the exact key is preferred
+            let prefix_of_other = bound.len() != sequence.len()
+                && bound[..shorter]
+                    .iter()
+                    .zip(&sequence[..shorter])
+                    .all(|(a, b)| a.overlaps(*b));
+            !(prefix_of_other || *bound == sequence)
+        });
+        self.bindings.push((sequence, action));
+    }
+
+    /// Remove the binding for the sequence, returning the action it was bound to
+    ///
+    /// # Errors
+    /// This function will return an error if `spec` is not a valid sequence.
+    pub fn unbind(&mut self, spec: &str) -> Result<Option<T>, KeymapError> {
+        let sequence = parse_sequence(spec)?;
+        Ok(self
+            .bindings
+            .iter()
+            .position(|(bound, _)| *bound == sequence)
+            .map(|idx| self.bindings.remove(idx).1))
+    }
+
+    /// Get the action bound to exactly this sequence, if any
+    ///
+    /// # Errors
+    /// This function will return an error if `spec` is not a valid sequence.
+    pub fn get(&self, spec: &str) -> Result<Option<&T>, KeymapError> {
+        let sequence = parse_sequence(spec)?;
+        Ok(self
+            .bindings
+            .iter()
+            .find(|(bound, _)| *bound == sequence)
+            .map(|(_, action)| action))
+    }
+
+    /// All the bound sequences and their actions, in the order they were bound
+    pub fn bindings(&self) -> impl Iterator<Item = (&[KeyPattern], &T)> {
+        self.bindings.iter().map(|(seq, a)| (seq.as_slice(), a))
+    }
+
+    /// Whether some keys are buffered, waiting for the rest of a sequence
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        let pending = self.pending();
+        !pending.keys.is_empty() && !self.timed_out(&pending)
+    }
+
+    /// Drop all the buffered keys
+    pub fn reset(&self) {
+        self.pending().keys.clear();
+    }
+
+    /// Feed the next key typed by the user to the matcher
+    pub fn feed(&self, key: KeyEvent) -> SequenceMatch<T> {
+        let mut pending = self.pending();
+        if self.timed_out(&pending) {
+            pending.keys.clear();
+        }
+        pending.keys.push(key);
+        pending.last = Some(Instant::now());
+
+        let mut result = self.find(&pending.keys);
+        if matches!(result, SequenceMatch::NoMatch) && pending.keys.len() > 1 {
+            // The key does not continue the sequence, try it on it's own
+            pending.keys.clear();
+            pending.keys.push(key);
+            result = self.find(&pending.keys);
+        }
+        if !matches!(result, SequenceMatch::Pending) {
+            pending.keys.clear();
+        }
+        result
+    }
+
+    // The buffer holds no invariants that a panic could break, so a poisoned
+    // lock is still usable
+    fn pending(&self) -> MutexGuard<'_, PendingKeys> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn timed_out(&self, pending: &PendingKeys) -> bool {
+        pending
+            .last
+            .is_some_and(|last| last.elapsed() > self.timeout)
+    }
+
+    fn find(&self, keys: &[KeyEvent]) -> SequenceMatch<T> {
+        let mut pending = false;
+        // The matching complete sequence with the least `<char>` patterns
+        let mut best: Option<(usize, &[KeyPattern], &T)> = None;
+        for (sequence, action) in &self.bindings {
+            if sequence.len() < keys.len() || !sequence.iter().zip(keys).all(|(p, k)| p.matches(*k))
+            {
+                continue;
+            }
+            if sequence.len() > keys.len() {
+                pending = true;
+                continue;
+            }
+            let wildcards = sequence
+                .iter()
+                .filter(|p| **p == KeyPattern::AnyChar)
+                .count();
+            if best.map_or(true, |(w, _, _)| wildcards < w) {
+                best = Some((wildcards, sequence, action));
+            }
+        }
+        match best {
+            Some((_, sequence, action)) => SequenceMatch::Matched {
+                action: action.clone(),
+                argument: sequence
+                    .iter()
+                    .zip(keys)
+                    .find(|(p, _)| **p == KeyPattern::AnyChar)
+                    .map(|(_, k)| *k),
+            },
+            None if pending => SequenceMatch::Pending,
+            None => SequenceMatch::NoMatch,
+        }
+    }
+}
+
+impl<T: Clone> Clone for KeySequenceMatcher<T> {
+    // The buffered keys are not cloned
+    fn clone(&self) -> Self {
+        Self {
+            bindings: self.bindings.clone(),
+            timeout: self.timeout,
+            pending: Mutex::new(PendingKeys::default()),
+        }
+    }
+}
+
+impl<T: Clone> Default for KeySequenceMatcher<T> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_sequence, sequence_to_string, KeyPattern, KeySequenceMatcher, SequenceMatch,
+    };
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::time::Duration;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_parse_sequence() {
+        assert_eq!(
+            parse_sequence("gg").unwrap(),
+            vec![KeyPattern::Key(key('g')), KeyPattern::Key(key('g'))]
+        );
+        assert_eq!(
+            parse_sequence("C-x C-c").unwrap(),
+            parse_sequence("C-xC-c").unwrap()
+        );
+        assert_eq!(
+            parse_sequence("m<char>").unwrap(),
+            vec![KeyPattern::Key(key('m')), KeyPattern::AnyChar]
+        );
+        assert_eq!(
+            parse_sequence("<PageDown>").unwrap(),
+            vec![KeyPattern::Key(KeyEvent::new(
+                KeyCode::PageDown,
+                KeyModifiers::NONE
+            ))]
+        );
+        assert!(parse_sequence("").is_err());
+        assert!(parse_sequence("g<Nope>").is_err());
+    }
+
+    #[test]
+    fn test_sequence_to_string() {
+        for spec in &["gg", "ZZ", "m<char>", "C-x C-c", "<Space>", "'<char>"] {
+            assert_eq!(&sequence_to_string(&parse_sequence(spec).unwrap()), spec);
+        }
+    }
+
+    #[test]
+    fn test_feed() {
+        let mut matcher = KeySequenceMatcher::default();
+        matcher.bind("gg", 1).unwrap();
+        matcher.bind("ZZ", 2).unwrap();
+        matcher.bind("m<char>", 3).unwrap();
+        matcher.bind("mm", 4).unwrap();
+
+        assert_eq!(matcher.feed(key('g')), SequenceMatch::Pending);
+        assert!(matcher.is_pending());
+        assert_eq!(
+            matcher.feed(key('g')),
+            SequenceMatch::Matched {
+                action: 1,
+                argument: None
+            }
+        );
+        assert!(!matcher.is_pending());
+
+        // A key that does not continue the sequence starts a new one
+        assert_eq!(matcher.feed(key('g')), SequenceMatch::Pending);
+        assert_eq!(matcher.feed(key('Z')), SequenceMatch::Pending);
+        assert_eq!(
+            matcher.feed(key('Z')),
+            SequenceMatch::Matched {
+                action: 2,
+                argument: None
+            }
+        );
+
+        // Exact keys win over `<char>`
+        matcher.feed(key('m'));
+        assert_eq!(
+            matcher.feed(key('m')),
+            SequenceMatch::Matched {
+                action: 4,
+                argument: None
+            }
+        );
+        matcher.feed(key('m'));
+        assert_eq!(
+            matcher.feed(key('x')),
+            SequenceMatch::Matched {
+                action: 3,
+                argument: Some(key('x'))
+            }
+        );
+
+        assert_eq!(matcher.feed(key('q')), SequenceMatch::NoMatch);
+        assert_eq!(
+            matcher.feed(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            SequenceMatch::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_bind_removes_prefixes() {
+        let mut matcher = KeySequenceMatcher::default();
+        matcher.bind("g", 1).unwrap();
+        matcher.bind("gg", 2).unwrap();
+        assert_eq!(matcher.get("g").unwrap(), None);
+        assert_eq!(matcher.get("gg").unwrap(), Some(&2));
+
+        matcher.bind("g", 3).unwrap();
+        assert_eq!(matcher.get("gg").unwrap(), None);
+        assert_eq!(
+            matcher.feed(key('g')),
+            SequenceMatch::Matched {
+                action: 3,
+                argument: None
+            }
+        );
+
+        matcher.bind("m<char>", 4).unwrap();
+        matcher.bind("mx", 5).unwrap();
+        assert_eq!(matcher.get("m<char>").unwrap(), Some(&4));
+        assert_eq!(matcher.unbind("mx").unwrap(), Some(5));
+        assert_eq!(matcher.unbind("mx").unwrap(), None);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut matcher = KeySequenceMatcher::new(Duration::from_millis(10));
+        matcher.bind("gg", 1).unwrap();
+        matcher.bind("G", 2).unwrap();
+
+        assert_eq!(matcher.feed(key('g')), SequenceMatch::Pending);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!matcher.is_pending());
+        assert_eq!(matcher.feed(key('g')), SequenceMatch::Pending);
+        matcher.reset();
+        assert_eq!(
+            matcher.feed(key('G')),
+            SequenceMatch::Matched {
+                action: 2,
+                argument: None
+            }
+        );
+    }
+}
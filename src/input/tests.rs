@@ -1,23 +1,22 @@
 #[cfg(feature = "search")]
 use crate::SearchMode;
-use crate::{input::InputEvent, LineNumbers, Pager};
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use crate::{
+    input::{InputEvent, PagerState},
+    LineNumbers, Pager,
+};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 
 // Just a transparent function to fix incompatiblity issues between
 // versions
 // TODO: Remove this later in favour of how handle_event should actually be called
 fn handle_input(ev: Event, p: &Pager) -> Option<InputEvent> {
-    p.input_classifier.classify_input(
-        ev,
-        p.upper_mark,
-        #[cfg(feature = "search")]
-        p.search_mode,
-        p.line_numbers,
-        // We set `message` to false explicitly, for the sake of correctness
-        // This will be tested inside a seperate so that it produces the result as expected
-        false,
-        p.rows,
-    )
+    let mut ps = PagerState::from_pager(p);
+    // We set `message` to false explicitly, for the sake of correctness
+    // This will be tested inside a seperate so that it produces the result as expected
+    ps.message = false;
+    p.input_classifier.classify_input(ev, &ps)
 }
 
 // Keyboard navigation
@@ -184,12 +183,11 @@ fn test_restore_prompt() {
             Some(InputEvent::RestorePrompt),
             pager.input_classifier.classify_input(
                 ev,
-                pager.upper_mark,
-                #[cfg(feature = "search")]
-                SearchMode::Unknown,
-                LineNumbers::Disabled,
-                true,
-                pager.rows
+                &PagerState {
+                    line_numbers: LineNumbers::Disabled,
+                    message: true,
+                    ..PagerState::from_pager(&pager)
+                }
             )
         );
     }
@@ -227,6 +225,70 @@ fn test_mouse_nav() {
             handle_input(ev, &pager)
         );
     }
+
+    {
+        pager.set_wheel_step(2);
+        let ev = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            row: 0,
+            column: 0,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert_eq!(
+            Some(InputEvent::UpdateUpperMark(pager.upper_mark + 2)),
+            handle_input(ev, &pager)
+        );
+    }
+}
+
+#[test]
+fn test_mouse_select() {
+    let mut pager = Pager::new().unwrap();
+    pager.upper_mark = 12;
+    pager.rows = 5;
+    let mouse = |kind, row| {
+        handle_input(
+            Event::Mouse(MouseEvent {
+                kind,
+                row,
+                column: 3,
+                modifiers: KeyModifiers::NONE,
+            }),
+            &pager,
+        )
+    };
+    assert_eq!(
+        mouse(MouseEventKind::Down(MouseButton::Left), 1),
+        Some(InputEvent::MouseClick(13))
+    );
+    assert_eq!(
+        mouse(MouseEventKind::Drag(MouseButton::Left), 3),
+        Some(InputEvent::MouseDrag(15))
+    );
+    assert_eq!(
+        mouse(MouseEventKind::Up(MouseButton::Left), 2),
+        Some(InputEvent::MouseRelease(14))
+    );
+    // Clicks on the prompt line are ignored, but releasing there ends the
+    // selection on the last row
+    assert_eq!(mouse(MouseEventKind::Down(MouseButton::Left), 4), None);
+    assert_eq!(
+        mouse(MouseEventKind::Up(MouseButton::Left), 4),
+        Some(InputEvent::MouseRelease(15))
+    );
+    assert_eq!(mouse(MouseEventKind::Down(MouseButton::Right), 1), None);
+
+    // Toggle mouse capture
+    let ev = Event::Key(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::ALT));
+    assert_eq!(
+        handle_input(ev, &pager),
+        Some(InputEvent::UpdateMouseCapture(true))
+    );
+    pager.set_mouse_capture(true);
+    assert_eq!(
+        handle_input(ev, &pager),
+        Some(InputEvent::UpdateMouseCapture(false))
+    );
 }
 
 #[test]
@@ -364,22 +426,20 @@ fn test_search_bindings() {
         assert_eq!(
             pager.input_classifier.classify_input(
                 next_event,
-                pager.upper_mark,
-                SearchMode::Forward,
-                pager.line_numbers,
-                false,
-                pager.rows
+                &PagerState {
+                    search_mode: SearchMode::Forward,
+                    ..PagerState::from_pager(&pager)
+                }
             ),
             Some(InputEvent::NextMatch)
         );
         assert_eq!(
             pager.input_classifier.classify_input(
                 prev_event,
-                pager.upper_mark,
-                SearchMode::Forward,
-                pager.line_numbers,
-                false,
-                pager.rows
+                &PagerState {
+                    search_mode: SearchMode::Forward,
+                    ..PagerState::from_pager(&pager)
+                }
             ),
             Some(InputEvent::PrevMatch)
         );
@@ -399,24 +459,258 @@ fn test_search_bindings() {
         assert_eq!(
             pager.input_classifier.classify_input(
                 next_event,
-                pager.upper_mark,
-                SearchMode::Reverse,
-                pager.line_numbers,
-                false,
-                pager.rows
+                &PagerState {
+                    search_mode: SearchMode::Reverse,
+                    ..PagerState::from_pager(&pager)
+                }
             ),
             Some(InputEvent::PrevMatch)
         );
         assert_eq!(
             pager.input_classifier.classify_input(
                 prev_event,
-                pager.upper_mark,
-                SearchMode::Reverse,
-                pager.line_numbers,
-                false,
-                pager.rows
+                &PagerState {
+                    search_mode: SearchMode::Reverse,
+                    ..PagerState::from_pager(&pager)
+                }
             ),
             Some(InputEvent::NextMatch)
         );
     }
 }
+
+#[test]
+#[cfg(feature = "search")]
+fn test_occur_bindings() {
+    use crate::input::{InputClassifier, Keymap};
+
+    let keymap = Keymap::default();
+    let ps = PagerState {
+        upper_mark: 12,
+        rows: 5,
+        cursor_line: 4,
+        occur: true,
+        ..PagerState::default()
+    };
+    let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+    // Up and down move the focus instead of scrolling, Enter goes to the line
+    for (ev, expected) in [
+        (key(KeyCode::Char('o')), InputEvent::Occur),
+        (key(KeyCode::Up), InputEvent::FocusLine(3)),
+        (key(KeyCode::Char('j')), InputEvent::FocusLine(5)),
+        (key(KeyCode::Enter), InputEvent::GoToOccurrence),
+    ] {
+        assert_eq!(keymap.classify_input(ev, &ps), Some(expected));
+    }
+    // A message is cleared first
+    let ps = PagerState {
+        message: true,
+        ..ps
+    };
+    assert_eq!(
+        keymap.classify_input(key(KeyCode::Enter), &ps),
+        Some(InputEvent::RestorePrompt)
+    );
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_search_scope_bindings() {
+    use crate::input::{InputClassifier, Keymap, SearchScope};
+
+    let classifier = Keymap::default();
+    let classify = |keys: &str, ps: &PagerState| {
+        let mut result = None;
+        for c in keys.chars() {
+            let ev = Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            result = classifier.classify_input(ev, ps);
+        }
+        result
+    };
+    let mut ps = PagerState::default();
+    assert_eq!(
+        classify("-s", &ps),
+        Some(InputEvent::SetSearchScope(SearchScope::Screen))
+    );
+    assert_eq!(
+        classify("Sa", &ps),
+        Some(InputEvent::SetSearchScope(SearchScope::Mark('a')))
+    );
+    // With the searches limited, `-s` searches all the lines again
+    ps.search_scope = Some((1, 10));
+    assert_eq!(
+        classify("-s", &ps),
+        Some(InputEvent::SetSearchScope(SearchScope::All))
+    );
+}
+
+#[test]
+fn test_count_prefix() {
+    let mut pager = Pager::new().unwrap();
+    pager.upper_mark = 12;
+    pager.rows = 5;
+    let classify = |code, count| {
+        pager.input_classifier.classify_input(
+            Event::Key(KeyEvent {
+                code,
+                modifiers: KeyModifiers::NONE,
+            }),
+            &PagerState {
+                count,
+                ..PagerState::from_pager(&pager)
+            },
+        )
+    };
+
+    assert_eq!(
+        Some(InputEvent::Number('7')),
+        classify(KeyCode::Char('7'), None)
+    );
+    assert_eq!(
+        Some(InputEvent::UpdateUpperMark(22)),
+        classify(KeyCode::Char('j'), Some(10))
+    );
+    assert_eq!(
+        Some(InputEvent::UpdateUpperMark(9)),
+        classify(KeyCode::Up, Some(3))
+    );
+    // rows is 5, so a page is 4 rows
+    assert_eq!(
+        Some(InputEvent::UpdateUpperMark(20)),
+        classify(KeyCode::PageDown, Some(2))
+    );
+    assert_eq!(
+        Some(InputEvent::GoToLine(50)),
+        classify(KeyCode::Char('G'), Some(50))
+    );
+    assert_eq!(
+        Some(InputEvent::GoToLine(1)),
+        classify(KeyCode::Char('g'), Some(1))
+    );
+    assert_eq!(
+        Some(InputEvent::GoToPercent(30)),
+        classify(KeyCode::Char('%'), Some(30))
+    );
+    assert_eq!(
+        Some(InputEvent::GoToPercent(0)),
+        classify(KeyCode::Char('%'), None)
+    );
+    assert_eq!(
+        Some(InputEvent::GoToLinePrompt),
+        classify(KeyCode::Char(':'), None)
+    );
+}
+
+#[test]
+fn test_mark_sequences() {
+    use crate::input::{CopyTarget, InputClassifier, Keymap};
+
+    let classifier = Keymap::default();
+    let classify = |c| {
+        classifier.classify_input(
+            Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)),
+            &PagerState::default(),
+        )
+    };
+    assert_eq!(classify('m'), None);
+    assert_eq!(classify('a'), Some(InputEvent::SetMark('a')));
+    assert_eq!(classify('\''), None);
+    assert_eq!(classify('a'), Some(InputEvent::GoToMark('a')));
+    assert_eq!(classify('y'), None);
+    assert_eq!(classify('y'), Some(InputEvent::Copy(CopyTarget::Lines(1))));
+    assert_eq!(classify('y'), None);
+    assert_eq!(classify('p'), Some(InputEvent::Copy(CopyTarget::Screen)));
+    assert_eq!(classify('y'), None);
+    assert_eq!(classify('s'), Some(InputEvent::Copy(CopyTarget::Selection)));
+    #[cfg(feature = "search")]
+    {
+        assert_eq!(classify('y'), None);
+        assert_eq!(classify('n'), Some(InputEvent::Copy(CopyTarget::Match)));
+    }
+    // Keys not part of a sequence still work as usual
+    assert_eq!(classify('q'), Some(InputEvent::Exit));
+    // A count copies that many lines
+    let ev = Event::Key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
+    let ps = PagerState {
+        count: Some(4),
+        ..PagerState::default()
+    };
+    assert_eq!(classifier.classify_input(ev, &ps), None);
+    assert_eq!(
+        classifier.classify_input(ev, &ps),
+        Some(InputEvent::Copy(CopyTarget::Lines(4)))
+    );
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_search_option_sequences() {
+    use crate::input::{InputClassifier, Keymap};
+    use crate::{SearchCase, SearchOptions};
+
+    let classifier = Keymap::default();
+    let mut ps = PagerState::default();
+    let mut classify = |keys: &str| {
+        let mut result = None;
+        for c in keys.chars() {
+            let ev = Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            result = classifier.classify_input(ev, &ps);
+        }
+        if let Some(InputEvent::UpdateSearchOptions(options)) = result {
+            ps.search_options = options;
+        }
+        result
+    };
+    let mut expected = SearchOptions::default();
+    // The case mode cycles through all modes
+    for case in &[
+        SearchCase::Insensitive,
+        SearchCase::Smart,
+        SearchCase::Sensitive,
+    ] {
+        expected.case = *case;
+        assert_eq!(
+            classify("-i"),
+            Some(InputEvent::UpdateSearchOptions(expected))
+        );
+    }
+    expected.literal = true;
+    assert_eq!(
+        classify("-l"),
+        Some(InputEvent::UpdateSearchOptions(expected))
+    );
+    expected.whole_word = true;
+    assert_eq!(
+        classify("-w"),
+        Some(InputEvent::UpdateSearchOptions(expected))
+    );
+    expected.literal = false;
+    assert_eq!(
+        classify("-l"),
+        Some(InputEvent::UpdateSearchOptions(expected))
+    );
+    expected.fuzzy = true;
+    assert_eq!(
+        classify("-f"),
+        Some(InputEvent::UpdateSearchOptions(expected))
+    );
+}
+
+#[test]
+fn test_pager_state() {
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    pager.set_text("one\ntwo three four\nfive\nsix");
+    pager.upper_mark = 2;
+    pager.prefix_num = "12".to_string();
+    pager.end_stream = true;
+    let ps = PagerState::from_pager(&pager);
+    assert_eq!(ps.upper_mark, 2);
+    assert_eq!(ps.cols, 10);
+    assert_eq!(ps.line_count, 5);
+    assert_eq!(ps.logical_line_count, 4);
+    assert_eq!(ps.cursor_line, 2);
+    assert_eq!(ps.count, Some(12));
+    assert!(ps.end_stream);
+    assert!(!ps.message);
+}
@@ -97,8 +97,25 @@
 )]
 #![deny(clippy::all)]
 #![warn(clippy::pedantic)]
+// Lints clippy gained after the code they point at was written, which is kept
+// as it is instead of being rewritten for them
+#![allow(
+    clippy::doc_lazy_continuation,
+    clippy::empty_line_after_doc_comments,
+    clippy::manual_assert,
+    clippy::needless_borrow,
+    clippy::uninlined_format_args,
+    clippy::unnecessary_semicolon
+)]
 
+mod buffer;
 pub mod error;
+#[cfg(feature = "search")]
+mod filter;
+#[cfg(feature = "search")]
+mod fuzzy;
+#[cfg(feature = "search")]
+mod highlight;
 mod init;
 pub mod input;
 #[cfg(any(feature = "tokio_lib", feature = "async_std_lib"))]
@@ -115,11 +132,11 @@ use error::AlternateScreenPagingError;
 #[cfg(any(feature = "tokio_lib", feature = "async_std_lib"))]
 pub use rt_wrappers::*;
 #[cfg(feature = "search")]
-pub use search::SearchMode;
+pub use search::{FuzzyOrder, MatchPosition, SearchCase, SearchMode, SearchOptions};
 #[cfg(feature = "static_output")]
 pub use static_pager::page_all;
-use std::{fmt, io::stdout};
-use std::{iter::Flatten, string::ToString, vec::IntoIter};
+use std::string::ToString;
+use std::{collections::HashMap, fmt, io::stdout};
 pub use utils::LineNumbers;
 
 #[cfg(any(feature = "tokio_lib", feature = "async_std_lib"))]
@@ -152,12 +169,19 @@ pub type ExitCallbacks = Vec<Box<dyn FnMut() + Send + Sync + 'static>>;
 // If the terminal is resized, we update the rows and columns and rewrap the
 // text
 
+// A callback registered with `Pager::add_custom_action`
+type CustomAction = Box<dyn FnMut(&mut Pager) -> input::CustomActionResult + Send + Sync + 'static>;
+
 /// A struct containing all configurations for the pager.
 ///
 /// This is used by all initializing functions
+#[allow(clippy::struct_excessive_bools)]
 pub struct Pager {
     // The output that is displayed wrapped to the available terminal width
     wrap_lines: Vec<Vec<String>>,
+    // The text of each logical line of `wrap_lines` as it was given. Wrapping
+    // drops the spaces at the end of rows, so this is what is matched and copied
+    text_lines: Vec<String>,
     // Configuration for line numbers. See [`LineNumbers`]
     pub(crate) line_numbers: LineNumbers,
     // The prompt displayed at the bottom wrapped to available terminal width
@@ -169,6 +193,8 @@ pub struct Pager {
     input_classifier: Box<dyn input::InputClassifier + Sync + Send>,
     // Functions to run when the pager quits
     exit_callbacks: Vec<Box<dyn FnMut() + Send + Sync + 'static>>,
+    // Actions defined by the application, run on `InputEvent::Custom`
+    pub(crate) custom_actions: HashMap<String, CustomAction>,
     // The behaviour to do when user quits the program using `q` or `Ctrl+C`
     // See [`ExitStrategy`] for available options
     exit_strategy: ExitStrategy,
@@ -186,15 +212,86 @@ pub struct Pager {
     pub(crate) upper_mark: usize,
     // Do we want to page if there's no overflow
     pub(crate) run_no_overflow: bool,
+    // The digits typed so far as a count for the next command. This is shown at
+    // the prompt while it is pending
+    pub(crate) prefix_num: String,
+    // Positions marked by the user, as the logical line at the top of the screen
+    pub(crate) marks: HashMap<char, usize>,
+    // Whether mouse events are captured
+    pub(crate) mouse_capture: bool,
+    // Number of rows scrolled for each step of the mouse wheel
+    pub(crate) wheel_step: usize,
+    // The logical line last clicked on, starting from 1
+    pub(crate) focused_line: Option<usize>,
+    // The largest number of bytes that are copied to the clipboard at once
+    pub(crate) clipboard_limit: usize,
+    // The logical lines selected with the mouse, as the line where the selection
+    // started and the line where it currently ends
+    pub(crate) selection: Option<(usize, usize)>,
+    // The output put aside while a temporary buffer like the help screen is shown
+    pub(crate) saved_content: Option<buffer::SavedContent>,
     // Stores the most recent search term
     #[cfg(feature = "search")]
-    search_term: Option<regex::Regex>,
+    search_term: Option<search::SearchTerm>,
     // Direction of search
     #[cfg(feature = "search")]
     search_mode: SearchMode,
-    // Lines where searches have a match
+    // Matches of the search, in the order they appear
+    #[cfg(feature = "search")]
+    pub(crate) search_idx: Vec<search::SearchMatch>,
+    // Index in `search_idx` of the current match
+    #[cfg(feature = "search")]
+    pub(crate) search_mark: usize,
+    // Number of logical lines displayed that were searched for matches
+    #[cfg(feature = "search")]
+    pub(crate) search_scanned: usize,
+    // Number of wrapped rows in the logical lines that were searched, which is
+    // the row the next line to search starts at
+    #[cfg(feature = "search")]
+    pub(crate) search_scanned_rows: usize,
+    // What the lines left to search for matches are searched for, if any
+    #[cfg(feature = "search")]
+    pub(crate) search_indexing: Option<search::Indexing>,
+    // Number of logical lines searched for matches at a time
+    #[cfg(feature = "search")]
+    pub(crate) search_chunk: usize,
+    // The first and last logical line searched, as numbered in the whole output,
+    // if searches are limited to some of the lines
+    #[cfg(feature = "search")]
+    pub(crate) search_scope: Option<(usize, usize)>,
+    // Number of rows kept above the current match when moving to it
+    #[cfg(feature = "search")]
+    pub(crate) match_context: usize,
+    // The style of the current match, other matches are shown in reverse video
     #[cfg(feature = "search")]
-    pub(crate) search_idx: Vec<usize>,
+    pub(crate) current_match_style: crossterm::style::ContentStyle,
+    // Whether to move to the matches found in lines added to the output
+    #[cfg(feature = "search")]
+    pub(crate) jump_to_new_matches: bool,
+    // The order `n` and `p` visit the matches of fuzzy searches in
+    #[cfg(feature = "search")]
+    pub(crate) fuzzy_order: FuzzyOrder,
+    // Previous search queries for each direction
+    #[cfg(feature = "search")]
+    pub(crate) search_history: utils::history::SearchHistory,
+    // Whether to search while the query is typed
+    #[cfg(feature = "search")]
+    pub(crate) incremental_search: bool,
+    // How queries are matched, unless changed for a single query
+    #[cfg(feature = "search")]
+    pub(crate) search_options: SearchOptions,
+    // The query of the current search, as the user typed it
+    #[cfg(feature = "search")]
+    pub(crate) search_query: Option<String>,
+    // The filter hiding the lines that do not match it
+    #[cfg(feature = "search")]
+    pub(crate) filter: Option<filter::Filter>,
+    // Number of lines shown around matching lines by filters
+    #[cfg(feature = "search")]
+    pub(crate) filter_context: usize,
+    // Patterns highlighted with their own style, in the order they were added
+    #[cfg(feature = "search")]
+    pub(crate) highlights: Vec<highlight::HighlightRule>,
     // Rows of the terminal
     pub(crate) rows: usize,
     // Columns of the terminal
@@ -231,13 +328,23 @@ impl Pager {
 
         Ok(Pager {
             wrap_lines: Vec::new(),
+            text_lines: Vec::new(),
             line_numbers: LineNumbers::Disabled,
             upper_mark: 0,
             prompt: wrap_str("minus", cols.into()),
             exit_strategy: ExitStrategy::ProcessQuit,
-            input_classifier: Box::new(input::DefaultInputHandler {}),
+            input_classifier: Box::new(input::DefaultInputHandler::default()),
             exit_callbacks: Vec::new(),
+            custom_actions: HashMap::new(),
             run_no_overflow: false,
+            prefix_num: String::new(),
+            marks: HashMap::new(),
+            mouse_capture: false,
+            wheel_step: 5,
+            focused_line: None,
+            selection: None,
+            clipboard_limit: utils::clipboard::DEFAULT_LIMIT,
+            saved_content: None,
             message: (None, false),
             lines: String::new(),
             end_stream: false,
@@ -247,6 +354,40 @@ impl Pager {
             search_mode: SearchMode::Unknown,
             #[cfg(feature = "search")]
             search_idx: Vec::new(),
+            #[cfg(feature = "search")]
+            search_mark: 0,
+            #[cfg(feature = "search")]
+            search_scanned: 0,
+            #[cfg(feature = "search")]
+            search_scanned_rows: 0,
+            #[cfg(feature = "search")]
+            search_indexing: None,
+            #[cfg(feature = "search")]
+            search_chunk: search::INDEX_CHUNK,
+            #[cfg(feature = "search")]
+            search_scope: None,
+            #[cfg(feature = "search")]
+            match_context: search::MATCH_CONTEXT,
+            #[cfg(feature = "search")]
+            current_match_style: highlight::current_match_style(),
+            #[cfg(feature = "search")]
+            jump_to_new_matches: false,
+            #[cfg(feature = "search")]
+            fuzzy_order: FuzzyOrder::Document,
+            #[cfg(feature = "search")]
+            search_history: utils::history::SearchHistory::default(),
+            #[cfg(feature = "search")]
+            incremental_search: true,
+            #[cfg(feature = "search")]
+            search_options: SearchOptions::default(),
+            #[cfg(feature = "search")]
+            search_query: None,
+            #[cfg(feature = "search")]
+            filter: None,
+            #[cfg(feature = "search")]
+            filter_context: 0,
+            #[cfg(feature = "search")]
+            highlights: Vec::new(),
             // Just to be safe in tests, keep at 1x1 size
             cols: cols as usize,
             rows: rows as usize,
@@ -266,7 +407,23 @@ impl Pager {
     pub fn set_text(&mut self, text: impl Into<String>) {
         let text: String = text.into();
         // self.lines = WrappedLines::from(Line::from_str(&text.into(), self.cols));
-        self.wrap_lines = text.lines().map(|l| wrap_str(l, self.cols)).collect();
+        let cols = self.cols;
+        let (text_lines, wrap_lines) = self.output_lines_mut();
+        *text_lines = text.lines().map(ToString::to_string).collect();
+        *wrap_lines = text_lines.iter().map(|l| wrap_str(l, cols)).collect();
+        #[cfg(feature = "search")]
+        {
+            if let Some(filter) = self.filter.as_mut() {
+                filter.reset(match &self.saved_content {
+                    Some(saved) => &saved.text_lines,
+                    None => &self.text_lines,
+                });
+            }
+            // The matches of the output put aside are found when it is restored
+            if self.search_term.is_some() && self.saved_content.is_none() {
+                search::set_match_indices(self);
+            }
+        }
     }
 
     /// Set line number to this setting
@@ -324,7 +481,12 @@ impl Pager {
         if prompt.contains('\n') {
             panic!("Prompt text cannot contain newlines");
         }
-        self.prompt = wrap_str(&prompt, self.cols);
+        let prompt = wrap_str(&prompt, self.cols);
+        match self.saved_content.as_mut() {
+            // Keep the prompt of the temporary buffer until the output is restored
+            Some(saved) => saved.prompt = prompt,
+            None => self.prompt = prompt,
+        }
     }
 
     /// Return a [`PagerMutex`] from this [`Pager`]. This is gated on `tokio_lib` or
@@ -363,12 +525,243 @@ impl Pager {
         self.exit_strategy = strategy;
     }
 
-    /// Returns the appropriate text for displaying.
+    /// Returns the filter, unless a temporary buffer is shown in place of the
+    /// output it filters
+    #[cfg(feature = "search")]
+    pub(crate) fn active_filter(&self) -> Option<&filter::Filter> {
+        self.filter
+            .as_ref()
+            .filter(|_| self.saved_content.is_none())
+    }
+
+    /// Returns the lines searches are limited to, unless a temporary buffer is
+    /// shown in place of the output they are numbered in
+    #[cfg(feature = "search")]
+    pub(crate) fn active_search_scope(&self) -> Option<(usize, usize)> {
+        self.search_scope.filter(|_| self.saved_content.is_none())
+    }
+
+    /// Returns what the search highlights in the logical line displayed at the
+    /// index `line`, if anything
+    ///
+    /// Lines outside of the lines searched are not highlighted, and the current
+    /// match is only known in the output, not in a temporary buffer.
+    #[cfg(feature = "search")]
+    pub(crate) fn search_highlight(&self, line: usize) -> Option<highlight::SearchHighlight<'_>> {
+        let term = self.search_term.as_ref()?;
+        if !search::in_scope(self.active_search_scope(), self.original_line(line + 1)) {
+            return None;
+        }
+        let current = self
+            .search_idx
+            .get(self.search_mark)
+            .filter(|m| m.line == line && self.saved_content.is_none())
+            .map(|m| m.range.clone());
+        Some(highlight::SearchHighlight {
+            term,
+            current,
+            current_style: self.current_match_style,
+        })
+    }
+
+    /// Returns the highlight rules to apply to the text displayed, which are none
+    /// while a temporary buffer is shown
+    #[cfg(feature = "search")]
+    pub(crate) fn shown_highlights(&self) -> &[highlight::HighlightRule] {
+        if self.saved_content.is_some() {
+            &[]
+        } else {
+            &self.highlights
+        }
+    }
+
+    /// Returns the number in the whole output of the logical line `line` as it
+    /// is displayed, both starting from 1
+    ///
+    /// This only differs from `line` when a filter hides some lines.
+    #[cfg_attr(not(feature = "search"), allow(clippy::unused_self))]
+    pub(crate) fn original_line(&self, line: usize) -> usize {
+        #[cfg(feature = "search")]
+        if let Some(filter) = self.active_filter() {
+            return filter
+                .shown()
+                .get(line.saturating_sub(1))
+                .or_else(|| filter.shown().last())
+                .map_or(1, |idx| idx + 1);
+        }
+        line
+    }
+
+    /// Returns the logical line as it is displayed for the line numbered
+    /// `original` in the whole output, both starting from 1
+    ///
+    /// When a filter hides the line, the first line shown after it is returned
+    /// instead.
+    #[cfg_attr(not(feature = "search"), allow(clippy::unused_self))]
+    pub(crate) fn shown_line(&self, original: usize) -> usize {
+        #[cfg(feature = "search")]
+        if let Some(filter) = self.active_filter() {
+            let shown = filter.shown();
+            let idx = shown.partition_point(|idx| *idx + 1 < original);
+            return idx.min(shown.len().saturating_sub(1)) + 1;
+        }
+        original
+    }
+
+    /// Show only the lines matching `pattern`, or not matching it if `inverted`
+    /// is true
+    ///
+    /// The pattern is a regular expression, matched with the options set by
+    /// [`Pager::set_search_options`]. As many lines as set with
+    /// [`Pager::set_filter_context`] are shown around each matching line. The
+    /// line numbers stay those of the whole output. Lines added later are
+    /// filtered too.
+    ///
+    /// The user can set a filter with `&`, where a pattern starting with `!`
+    /// is inverted.
+    ///
+    /// ## Errors
+    /// This function will return an error if `pattern` is not a valid regular
+    /// expression
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_filter("ERROR|WARN", false).unwrap();
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_filter(&mut self, pattern: &str, inverted: bool) -> Result<(), error::RegexError> {
+        let regex = self.search_options.compile(pattern)?;
+        self.replace_filter(Some(filter::Filter::new(
+            regex,
+            inverted,
+            self.filter_context,
+        )));
+        Ok(())
+    }
+
+    /// Show all the lines again after [`Pager::set_filter`]
+    ///
+    /// The line that was at the top of the screen stays there.
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn clear_filter(&mut self) {
+        self.replace_filter(None);
+    }
+
+    /// Set the number of lines shown before and after each line matching a
+    /// filter
+    ///
+    /// This applies to the filters set after calling it. The user can give
+    /// the number of lines as count before `&` instead.
+    ///
+    /// By default this is set to 0
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_filter_context(2);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_filter_context(&mut self, lines: usize) {
+        self.filter_context = lines;
+    }
+
+    /// Highlight the matches of `pattern` with `style`, under the name `name`
+    ///
+    /// Highlights stay shown alongside the search, so that several patterns can
+    /// be watched at once. A highlight added with the name of an existing one
+    /// replaces it. Where the matches of highlights overlap, the highlight added
+    /// first is shown, and the search match is shown over both.
+    ///
+    /// The user can add highlights with `+`, and the help screen lists them.
+    ///
+    /// ## Errors
+    /// This function will return an error if `pattern` is not a valid regular
+    /// expression
+    ///
+    /// ```
+    /// use crossterm::style::{Color, ContentStyle};
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// let mut style = ContentStyle::new();
+    /// style.foreground_color = Some(Color::Red);
+    /// pager.add_highlight("errors", "ERROR", style).unwrap();
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn add_highlight(
+        &mut self,
+        name: impl Into<String>,
+        pattern: &str,
+        style: crossterm::style::ContentStyle,
+    ) -> Result<(), error::RegexError> {
+        let rule = highlight::HighlightRule {
+            name: name.into(),
+            pattern: regex::Regex::new(pattern)?,
+            style,
+        };
+        match self.highlights.iter_mut().find(|r| r.name == rule.name) {
+            Some(existing) => *existing = rule,
+            None => self.highlights.push(rule),
+        }
+        Ok(())
+    }
+
+    /// Remove the highlight added under the name `name`
+    ///
+    /// Returns false if there is no highlight with this name
+    ///
+    /// ```
+    /// use crossterm::style::ContentStyle;
+    /// use minus::Pager;
     ///
-    /// Nrmally it will return `self.lines`
-    /// In case of a search, `self.search_lines` is returned
-    pub(crate) fn get_lines(&self) -> Vec<Vec<String>> {
-        self.wrap_lines.clone()
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.add_highlight("errors", "ERROR", ContentStyle::new()).unwrap();
+    /// assert!(pager.remove_highlight("errors"));
+    /// assert!(!pager.remove_highlight("errors"));
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn remove_highlight(&mut self, name: &str) -> bool {
+        let len = self.highlights.len();
+        self.highlights.retain(|r| r.name != name);
+        self.highlights.len() != len
+    }
+
+    /// Remove all the highlights added with [`Pager::add_highlight`]
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn clear_highlights(&mut self) {
+        self.highlights.clear();
+    }
+
+    /// Replace the filter, keeping the line at the top of the screen if it is
+    /// still shown
+    #[cfg(feature = "search")]
+    pub(crate) fn replace_filter(&mut self, filter: Option<filter::Filter>) {
+        let top = self.original_line(self.line_of_row(self.upper_mark));
+        self.filter = filter;
+        if let Some(filter) = self.filter.as_mut() {
+            filter.update(match &self.saved_content {
+                Some(saved) => &saved.text_lines,
+                None => &self.text_lines,
+            });
+        }
+        // These are lines as displayed, which change with the filter
+        self.focused_line = None;
+        self.selection = None;
+        if self.saved_content.is_none() {
+            self.upper_mark = self.row_of_line(self.shown_line(top));
+            if self.search_term.is_some() {
+                search::set_match_indices(self);
+            }
+        }
     }
 
     /// Set whether to display pager if there's less data than
@@ -386,6 +779,451 @@ impl Pager {
         self.run_no_overflow = value;
     }
 
+    /// Set whether mouse events are captured by the pager
+    ///
+    /// With mouse capture, the mouse wheel scrolls the output, clicking a line
+    /// focuses it and dragging over lines selects them and copies them to the
+    /// clipboard once the button is released. Copying uses the OSC 52 escape
+    /// sequence, which must be supported by the terminal. Without mouse
+    /// capture, the terminal's own text selection works instead. The user
+    /// can switch between both with `Alt+M`.
+    ///
+    /// By default this is set to false
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_mouse_capture(true);
+    /// ```
+    pub fn set_mouse_capture(&mut self, value: bool) {
+        self.mouse_capture = value;
+    }
+
+    /// Set the number of rows to scroll for each step of the mouse wheel
+    ///
+    /// By default this is set to 5
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_wheel_step(3);
+    /// ```
+    pub fn set_wheel_step(&mut self, step: usize) {
+        self.wheel_step = step;
+    }
+
+    /// Set the largest number of bytes that are copied to the clipboard at once
+    ///
+    /// Terminals limit the size of the escape sequence used for copying, and
+    /// some drop it entirely when it is too large. If the user tries to copy
+    /// more than this, nothing is copied and a message is shown instead.
+    ///
+    /// By default this is set to 74,994 bytes, which are sent as 100,000 bytes
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_clipboard_limit(1_000_000);
+    /// ```
+    pub fn set_clipboard_limit(&mut self, bytes: usize) {
+        self.clipboard_limit = bytes;
+    }
+
+    /// Save the search history to the file at `path`
+    ///
+    /// The queries already saved in the file are added to the history, and the
+    /// file is rewritten after each search. The file does not need to exist yet.
+    /// Failing to write the file later on is silently ignored, so that the
+    /// user is not interrupted while searching.
+    ///
+    /// By default the history is only kept in memory
+    ///
+    /// ## Errors
+    /// This function will return an error if the file exists but cannot be read
+    ///
+    /// ```no_run
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_search_history_file("/home/me/.myapp_history").unwrap();
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_search_history_file(
+        &mut self,
+        path: impl Into<std::path::PathBuf>,
+    ) -> std::io::Result<()> {
+        self.search_history.set_path(path.into())
+    }
+
+    /// Set the number of queries kept in the search history of each direction
+    ///
+    /// By default this is set to 100
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_search_history_limit(500);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_search_history_limit(&mut self, limit: usize) {
+        self.search_history.set_limit(limit);
+    }
+
+    /// Add `query` to the search history of the direction `mode`
+    ///
+    /// The query becomes the most recent entry, which is the first one shown
+    /// when the user presses Up at the search prompt. An earlier copy of the
+    /// same query is removed. Forward and reverse searches have separate
+    /// histories; [`SearchMode::Unknown`] adds to the forward one.
+    ///
+    /// ```
+    /// use minus::{Pager, SearchMode};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.add_search_history(SearchMode::Forward, "error|warning");
+    /// assert_eq!(pager.search_history(SearchMode::Forward), ["error|warning"]);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn add_search_history(&mut self, mode: SearchMode, query: impl Into<String>) {
+        self.search_history.add(mode, query.into());
+    }
+
+    /// Set whether to search while the query is typed
+    ///
+    /// With incremental search, the matches are highlighted and the pager
+    /// moves to the first match as soon as the user stops typing. Queries that
+    /// are not valid regular expressions yet are skipped. Cancelling the search
+    /// with `Esc` returns to where it started, with the previous search term.
+    ///
+    /// By default this is set to true
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_incremental_search(false);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_incremental_search(&mut self, value: bool) {
+        self.incremental_search = value;
+    }
+
+    /// Set whether to move to the matches of the search found in text added
+    /// to the output
+    ///
+    /// Matches in text added while the pager is running are always found, so
+    /// that `n` and `p` reach them. With this on, the pager also moves to the
+    /// first of them as they come, like `tail -f` that stops at what you look
+    /// for.
+    ///
+    /// By default this is set to false
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_jump_to_new_matches(true);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_jump_to_new_matches(&mut self, value: bool) {
+        self.jump_to_new_matches = value;
+    }
+
+    /// Set the number of rows kept above the current search match when moving
+    /// to it
+    ///
+    /// The rows before the match give some context to it, instead of it sitting
+    /// at the very top of the screen. Fewer rows are kept on small screens, so
+    /// that the match is always shown. Set it to 0 to show the match on the top
+    /// row.
+    ///
+    /// By default this is set to 3
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_match_context(5);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_match_context(&mut self, rows: usize) {
+        self.match_context = rows;
+    }
+
+    /// Set the style of the current search match
+    ///
+    /// The current match, which `n` and `p` move from, is shown in this style
+    /// while the other matches are shown in reverse video. A style with only
+    /// the reverse attribute shows it like the other matches.
+    ///
+    /// By default the current match is shown in black on yellow
+    ///
+    /// ```
+    /// use crossterm::style::{Attribute, Color, ContentStyle};
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// let mut style = ContentStyle::new();
+    /// style.background_color = Some(Color::Green);
+    /// style.attributes.set(Attribute::Bold);
+    /// pager.set_current_match_style(style);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_current_match_style(&mut self, style: crossterm::style::ContentStyle) {
+        self.current_match_style = style;
+    }
+
+    /// Set the order in which `n` and `p` visit the matches of fuzzy searches
+    ///
+    /// With [`FuzzyOrder::Score`], a fuzzy search moves to the best match and
+    /// `n` goes on to the next best one, like fzf. The matches are numbered in
+    /// that order at the prompt. Other searches are not affected.
+    ///
+    /// By default this is set to [`FuzzyOrder::Document`]
+    ///
+    /// ```
+    /// use minus::{FuzzyOrder, Pager};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_fuzzy_order(FuzzyOrder::Score);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_fuzzy_order(&mut self, order: FuzzyOrder) {
+        self.fuzzy_order = order;
+    }
+
+    /// Only search the logical lines from `start` to `end`, both starting from 1
+    ///
+    /// Matches outside of these lines are not highlighted and `n` and `p` do not
+    /// move to them. The lines are numbered as in the whole output, even when a
+    /// filter hides some of them. The user sees the lines searched at the
+    /// prompt, and can limit searches to the screen with `-s` or to the lines
+    /// from a mark with `S` followed by the mark.
+    ///
+    /// ```
+    /// use minus::Pager;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_search_scope(10, 20);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_search_scope(&mut self, start: usize, end: usize) {
+        let start = start.max(1);
+        self.search_scope = Some((start.min(end), start.max(end)));
+        self.update_search_scope();
+    }
+
+    /// Search all the lines again after [`Pager::set_search_scope`]
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn clear_search_scope(&mut self) {
+        self.search_scope = None;
+        self.update_search_scope();
+    }
+
+    // Find the matches of the current search in the lines now searched
+    #[cfg(feature = "search")]
+    fn update_search_scope(&mut self) {
+        // The matches are found again once the temporary buffer is closed
+        if self.search_term.is_some() && self.saved_content.is_none() {
+            self.search_indexing = None;
+            self.search_mark = 0;
+            search::set_match_indices(self);
+        }
+    }
+
+    /// Search for `query`, like the user does with `/` or `?`
+    ///
+    /// The query is matched with the options set by
+    /// [`Pager::set_search_options`], use [`Pager::search_with_options`] to
+    /// give other options. The pager moves to the first match from the top of
+    /// the screen, highlights the matches and tells which match it is at. The
+    /// query is added to the search history. Afterwards, `n` and `p` move
+    /// between the matches like after a search by the user.
+    ///
+    /// With a `PagerMutex`, call this on the locked pager. The screen is
+    /// updated even if the user does nothing.
+    ///
+    /// Large outputs are searched a part at a time while the pager runs, so
+    /// [`Pager::search_matches`] may not have all the matches right away.
+    ///
+    /// ## Errors
+    /// This function will return an error if `query` is not a valid regular
+    /// expression. The search is left as it was.
+    ///
+    /// ```
+    /// use minus::{Pager, SearchMode};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_text("starting\nthread 'main' panicked\ndone");
+    /// pager.search("panic", SearchMode::Forward).unwrap();
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn search(&mut self, query: &str, mode: SearchMode) -> Result<(), error::RegexError> {
+        self.search_with_options(query, mode, self.search_options)
+    }
+
+    /// Search for `query` with the given `options`, like [`Pager::search`]
+    ///
+    /// The options only apply to this search, like the options toggled at the
+    /// search prompt.
+    ///
+    /// ## Errors
+    /// This function will return an error if `query` is not a valid regular
+    /// expression. The search is left as it was.
+    ///
+    /// ```
+    /// use minus::{Pager, SearchCase, SearchMode, SearchOptions};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_text("FAILED: test_one\nok: test_two");
+    /// let mut options = SearchOptions::default();
+    /// options.case = SearchCase::Insensitive;
+    /// pager
+    ///     .search_with_options("failed", SearchMode::Forward, options)
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn search_with_options(
+        &mut self,
+        query: &str,
+        mode: SearchMode,
+        options: SearchOptions,
+    ) -> Result<(), error::RegexError> {
+        search::run_search(self, query.to_string(), mode, options, self.upper_mark)?;
+        Ok(())
+    }
+
+    /// Move to the next match of the search, like `n`
+    ///
+    /// After a reverse search, this moves to the match before the current one.
+    /// Returns the match the pager moved to, or `None` if there is no search or
+    /// it has no matches.
+    ///
+    /// ```
+    /// use minus::{Pager, SearchMode};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_text("FAIL one\nok\nFAIL two");
+    /// pager.search("FAIL", SearchMode::Forward).unwrap();
+    /// if let Some(found) = pager.next_match() {
+    ///     println!("Failure at line {}", found.line);
+    /// }
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn next_match(&mut self) -> Option<MatchPosition> {
+        self.search_term.as_ref()?;
+        search::go_to_match(self, true);
+        self.current_match()
+    }
+
+    /// Move to the previous match of the search, like `p`
+    ///
+    /// After a reverse search, this moves to the match after the current one.
+    /// Returns the match the pager moved to, or `None` if there is no search or
+    /// it has no matches.
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn prev_match(&mut self) -> Option<MatchPosition> {
+        self.search_term.as_ref()?;
+        search::go_to_match(self, false);
+        self.current_match()
+    }
+
+    /// Stop searching and remove the highlights of the matches
+    ///
+    /// The query stays in the search history.
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn clear_search(&mut self) {
+        self.search_term = None;
+        self.search_query = None;
+        self.search_idx.clear();
+        self.search_mark = 0;
+        self.search_indexing = None;
+    }
+
+    /// Returns the positions of all the matches of the search, in the order
+    /// they appear in the output
+    ///
+    /// Matches in lines hidden by a filter are not included.
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    #[must_use]
+    pub fn search_matches(&self) -> Vec<MatchPosition> {
+        self.search_idx
+            .iter()
+            .map(|m| self.match_position(m))
+            .collect()
+    }
+
+    /// Returns the position of the match the pager is at, if there is a search
+    /// with matches
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    #[must_use]
+    pub fn current_match(&self) -> Option<MatchPosition> {
+        self.search_idx
+            .get(self.search_mark)
+            .map(|m| self.match_position(m))
+    }
+
+    #[cfg(feature = "search")]
+    fn match_position(&self, m: &search::SearchMatch) -> MatchPosition {
+        MatchPosition {
+            line: self.original_line(m.line + 1),
+            range: m.range.clone(),
+        }
+    }
+
+    /// Set how search queries match the text
+    ///
+    /// The user can change the options for the rest of the session with the
+    /// `-i`, `-l` and `-w` keys, or for a single query with `Alt+C`, `Ctrl+R`
+    /// and `Alt+W` at the search prompt. The options that are active are shown
+    /// before the prompt.
+    ///
+    /// By default searches are case sensitive regular expressions
+    ///
+    /// ```
+    /// use minus::{Pager, SearchCase, SearchOptions};
+    ///
+    /// let mut options = SearchOptions::default();
+    /// options.case = SearchCase::Insensitive;
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_search_options(options);
+    /// ```
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    pub fn set_search_options(&mut self, options: SearchOptions) {
+        self.search_options = options;
+    }
+
+    /// Get the search history of the direction `mode`, from the oldest query
+    #[cfg(feature = "search")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+    #[must_use]
+    pub fn search_history(&self, mode: SearchMode) -> &[String] {
+        self.search_history.entries(mode)
+    }
+
     /// Appends text to the pager output
     ///
     /// This function will automatically split the lines, if they overflow
@@ -399,28 +1237,28 @@ impl Pager {
         let string = string.into();
         if string.ends_with('\n') {
             self.lines.push_str(&string);
-            self.wrap_lines.append(
-                &mut self
-                    .lines
-                    .lines()
-                    .map(|l| wrap_str(l, self.cols))
-                    .collect::<Vec<Vec<String>>>(),
-            );
-            self.lines.clear();
+            let lines = std::mem::take(&mut self.lines);
+            self.append_lines(lines.lines());
         } else if string.contains('\n') {
-            let mut lines = string.lines().collect::<Vec<&str>>();
+            let lines = string.lines().collect::<Vec<&str>>();
             let line_count = lines.len();
-            let push_lines = &mut lines[0..line_count - 1];
-            self.wrap_lines.append(
-                &mut push_lines
-                    .iter()
-                    .map(|l| wrap_str(l, self.cols))
-                    .collect::<Vec<Vec<String>>>(),
-            );
+            self.append_lines(lines[0..line_count - 1].iter().copied());
             self.lines.push_str(lines[line_count - 1]);
         } else {
             self.lines.push_str(&string);
         }
+        #[cfg(feature = "search")]
+        {
+            if let Some(filter) = self.filter.as_mut() {
+                filter.update(match &self.saved_content {
+                    Some(saved) => &saved.text_lines,
+                    None => &self.text_lines,
+                });
+            }
+            if self.search_term.is_some() && self.saved_content.is_none() {
+                search::add_new_matches(self);
+            }
+        }
     }
 
     /// Hints the running pager that no more data is coming
@@ -439,21 +1277,172 @@ impl Pager {
 
     /// Readjust the text to new terminal size
     pub(crate) fn readjust_wraps(&mut self) {
-        rewrap_lines(&mut self.wrap_lines, self.cols);
-        if self.message.0.is_some() {
-            rewrap(&mut self.message.0.as_mut().unwrap(), self.cols);
+        rewrap_lines(&mut self.wrap_lines, &self.text_lines, self.cols);
+        if let Some(saved) = self.saved_content.as_mut() {
+            rewrap_lines(&mut saved.wrap_lines, &saved.text_lines, self.cols);
+        }
+        if let Some(message) = self.message.0.as_mut() {
+            rewrap(message, self.cols);
         }
         rewrap(&mut self.prompt, self.cols);
+        if let Some(saved) = self.saved_content.as_mut() {
+            rewrap(&mut saved.prompt, self.cols);
+        }
+        // The rows of the matches changed
+        #[cfg(feature = "search")]
+        if self.search_term.is_some() && self.saved_content.is_none() {
+            search::set_match_indices(self);
+        }
     }
 
-    /// Returns all the text by flattening them into a single vector of strings
-    pub(crate) fn get_flattened_lines(&self) -> Flatten<IntoIter<Vec<String>>> {
-        self.get_lines().into_iter().flatten()
+    /// Returns the text and the wrapped rows of the lines of the output, which
+    /// are put aside while a temporary buffer is shown
+    fn output_lines_mut(&mut self) -> (&mut Vec<String>, &mut Vec<Vec<String>>) {
+        match self.saved_content.as_mut() {
+            Some(saved) => (&mut saved.text_lines, &mut saved.wrap_lines),
+            None => (&mut self.text_lines, &mut self.wrap_lines),
+        }
+    }
+
+    /// Add `lines` at the end of the output, wrapped to the terminal width
+    fn append_lines<'a>(&mut self, lines: impl Iterator<Item = &'a str>) {
+        let cols = self.cols;
+        let (text_lines, wrap_lines) = self.output_lines_mut();
+        for line in lines {
+            wrap_lines.push(wrap_str(line, cols));
+            text_lines.push(line.to_string());
+        }
+    }
+
+    /// Returns the indices in `wrap_lines` and `text_lines` of the logical lines
+    /// displayed, from the one displayed at the index `from` on
+    fn shown_indices(&self, from: usize) -> impl Iterator<Item = usize> + '_ {
+        #[cfg(feature = "search")]
+        let shown = self.active_filter().map(filter::Filter::shown);
+        #[cfg(not(feature = "search"))]
+        let shown: Option<&[usize]> = None;
+        let filtered = shown.map(|shown| shown.get(from..).unwrap_or_default().iter().copied());
+        let all = shown.is_none().then_some(from..self.wrap_lines.len());
+        filtered
+            .into_iter()
+            .flatten()
+            .chain(all.into_iter().flatten())
+    }
+
+    /// Returns the index in `wrap_lines` and `text_lines` of the logical line
+    /// displayed at the index `idx`
+    fn shown_index(&self, idx: usize) -> Option<usize> {
+        #[cfg(feature = "search")]
+        if let Some(filter) = self.active_filter() {
+            return filter.shown().get(idx).copied();
+        }
+        (idx < self.wrap_lines.len()).then_some(idx)
+    }
+
+    /// Returns the logical lines displayed, which are only those shown by the
+    /// filter if there is one
+    pub(crate) fn shown_lines(&self) -> impl Iterator<Item = &Vec<String>> + '_ {
+        self.shown_indices(0).map(move |idx| &self.wrap_lines[idx])
+    }
+
+    /// Returns the text of the logical lines displayed, as it was given
+    pub(crate) fn shown_texts(&self) -> impl Iterator<Item = &str> + '_ {
+        self.shown_indices(0)
+            .map(move |idx| self.text_lines[idx].as_str())
+    }
+
+    /// Returns the rows of the logical line displayed at the index `idx`
+    pub(crate) fn shown_rows(&self, idx: usize) -> Option<&Vec<String>> {
+        self.shown_index(idx).map(|idx| &self.wrap_lines[idx])
+    }
+
+    /// Returns the text of the logical line displayed at the index `idx`, as it
+    /// was given
+    #[cfg(feature = "search")]
+    pub(crate) fn shown_text(&self, idx: usize) -> Option<&str> {
+        self.shown_index(idx)
+            .map(|idx| self.text_lines[idx].as_str())
     }
 
     /// Returns the number of lines the [`Pager`] currently holds
     pub(crate) fn num_lines(&self) -> usize {
-        self.get_flattened_lines().count()
+        self.shown_lines().map(Vec::len).sum()
+    }
+
+    /// Returns the number of logical lines the [`Pager`] currently holds
+    pub(crate) fn num_logical_lines(&self) -> usize {
+        #[cfg(feature = "search")]
+        if let Some(filter) = self.active_filter() {
+            return filter.shown().len();
+        }
+        self.wrap_lines.len()
+    }
+
+    /// Returns the index of the first wrapped row of the logical line `line`
+    ///
+    /// `line` starts from 1, like the line numbers shown to the user. Lines past
+    /// the end are clamped to the last line.
+    pub(crate) fn row_of_line(&self, line: usize) -> usize {
+        let line = line.clamp(1, self.num_logical_lines().max(1));
+        self.shown_lines().take(line - 1).map(Vec::len).sum()
+    }
+
+    /// Returns the logical line, starting from 1, that the wrapped row `row` is
+    /// part of
+    pub(crate) fn line_of_row(&self, row: usize) -> usize {
+        let mut rows = 0;
+        for (idx, line) in self.shown_lines().enumerate() {
+            rows += line.len();
+            if row < rows {
+                return idx + 1;
+            }
+        }
+        self.num_logical_lines().max(1)
+    }
+
+    /// Returns the logical line the user is at, starting from 1
+    ///
+    /// This is the focused line if it is on the screen, otherwise the line at the
+    /// top of the screen.
+    pub(crate) fn cursor_line(&self) -> usize {
+        let (top, bottom) = self.visible_lines();
+        match self.focused_line {
+            Some(line) if (top..=bottom).contains(&line) => line,
+            _ => top,
+        }
+    }
+
+    /// Returns the text of the logical lines from `start` to `end`, both starting
+    /// from 1, without any escape sequences
+    pub(crate) fn text_of_lines(&self, start: usize, end: usize) -> String {
+        let (start, end) = (start.min(end).max(1), start.max(end));
+        self.shown_texts()
+            .skip(start - 1)
+            .take(end - start + 1)
+            .map(utils::ansi::strip_ansi)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the text of the lines selected with the mouse, if any
+    pub(crate) fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection?;
+        Some(self.text_of_lines(start, end))
+    }
+
+    /// Returns the first and last logical line that are visible on the screen
+    pub(crate) fn visible_lines(&self) -> (usize, usize) {
+        (
+            self.line_of_row(self.upper_mark),
+            self.line_of_row(self.upper_mark.saturating_add(self.rows.saturating_sub(2))),
+        )
+    }
+
+    /// Returns the logical line at the given percentage of the output, starting
+    /// from 1
+    pub(crate) fn line_at_percent(&self, percent: usize) -> usize {
+        let percent = percent.min(100);
+        (self.num_logical_lines().saturating_mul(percent) / 100).max(1)
     }
 
     /// Set custom input handler function
@@ -464,6 +1453,39 @@ impl Pager {
         self.input_classifier = handler;
     }
 
+    /// Use a [`Keymap`](input::Keymap) as the input handler
+    ///
+    /// This is a shorthand for calling [`Pager::set_input_handler`] with the
+    /// keymap
+    ///
+    /// Example
+    /// ```
+    /// use minus::{input::Keymap, Pager};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_keymap(Keymap::from_config("b = page_up").unwrap());
+    /// ```
+    pub fn set_keymap(&mut self, keymap: input::Keymap) {
+        self.input_classifier = Box::new(keymap);
+    }
+
+    /// Use one of the built-in keymaps as the input handler
+    ///
+    /// See [`KeymapPreset`](input::KeymapPreset) for the available presets. To
+    /// change some of the bindings of a preset, use [`Keymap::preset`](input::Keymap::preset)
+    /// and pass the result to [`Pager::set_keymap`] instead.
+    ///
+    /// Example
+    /// ```
+    /// use minus::{input::KeymapPreset, Pager};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.set_keymap_preset(KeymapPreset::Less);
+    /// ```
+    pub fn set_keymap_preset(&mut self, preset: input::KeymapPreset) {
+        self.set_keymap(input::Keymap::preset(preset));
+    }
+
     // Runs the exit callbacks
     pub(crate) fn exit(&mut self) {
         for func in &mut self.exit_callbacks {
@@ -485,6 +1507,45 @@ impl Pager {
     pub fn add_exit_callback(&mut self, cb: impl FnMut() + Send + Sync + 'static) {
         self.exit_callbacks.push(Box::new(cb));
     }
+
+    /// Register a custom action under `name`
+    ///
+    /// The action runs whenever the input handler returns
+    /// [`InputEvent::Custom`](input::InputEvent::Custom) with this name. With a
+    /// [`Keymap`](input::Keymap), bind a key to
+    /// [`Action::Custom`](input::Action::Custom), or write `custom:<name>` in
+    /// the configuration. The callback gets mutable access to the pager and
+    /// returns what the pager should do next. Registering another action under
+    /// the same name replaces the previous one.
+    ///
+    /// Example
+    /// ```
+    /// use minus::{input::{CustomActionResult, Keymap}, Pager};
+    ///
+    /// let mut pager = Pager::new().unwrap();
+    /// pager.add_custom_action("clear", |pager| {
+    ///     pager.set_text("");
+    ///     CustomActionResult::Message("Output cleared".to_string())
+    /// });
+    /// pager.set_keymap(Keymap::from_config("x = custom:clear").unwrap());
+    /// ```
+    pub fn add_custom_action(
+        &mut self,
+        name: impl Into<String>,
+        cb: impl FnMut(&mut Pager) -> input::CustomActionResult + Send + Sync + 'static,
+    ) {
+        self.custom_actions.insert(name.into(), Box::new(cb));
+    }
+
+    /// Run the custom action registered under `name`, if any
+    pub(crate) fn run_custom_action(&mut self, name: &str) -> Option<input::CustomActionResult> {
+        // Take the callback out while it runs, so that it can borrow the pager
+        let mut cb = self.custom_actions.remove(name)?;
+        let result = cb(self);
+        // Don't overwrite an action that the callback registered in it's place
+        self.custom_actions.entry(name.to_string()).or_insert(cb);
+        Some(result)
+    }
 }
 
 impl std::default::Default for Pager {
@@ -513,10 +1574,11 @@ pub enum ExitStrategy {
 }
 
 /// Rewrap already wrapped vector of lines based on the number of columns
-pub(crate) fn rewrap_lines(lines: &mut Vec<Vec<String>>, cols: usize) {
-    for line in lines {
-        rewrap(line, cols);
-    }
+///
+/// The lines are wrapped again from `text_lines`, their text as it was given, so
+/// that the spaces dropped at the end of rows are not lost
+pub(crate) fn rewrap_lines(lines: &mut Vec<Vec<String>>, text_lines: &[String], cols: usize) {
+    *lines = text_lines.iter().map(|l| wrap_str(l, cols)).collect();
 }
 
 /// Rewrap a single line based on the number of columns
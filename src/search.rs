@@ -1,13 +1,18 @@
 #![allow(unused_imports)]
-use crate::error::AlternateScreenPagingError;
-use crate::Pager;
-use crossterm::{
-    cursor::{self, MoveTo},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    style::Attribute,
-    terminal::{Clear, ClearType},
+use crate::{
+    buffer,
+    error::AlternateScreenPagingError,
+    fuzzy::{self, Fuzzy},
+    highlight::row_starts,
+    utils::{
+        ansi::{strip_ansi, Stripped},
+        draw,
+        prompt::PromptHandler,
+    },
+    Pager,
 };
-use std::{convert::TryFrom, time::Duration};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 #[cfg_attr(docsrs, doc(cfg(feature = "search")))]
@@ -23,157 +28,1192 @@ pub enum SearchMode {
     Unknown,
 }
 
-/// Fetch the search query asynchronously
-#[cfg(feature = "search")]
-pub(crate) fn fetch_input(
-    out: &mut impl std::io::Write,
-    search_mode: SearchMode,
-    rows: usize,
-) -> Result<String, AlternateScreenPagingError> {
-    // Place the cursor at the beginning of very prompt line, clear
-    // the prompt and show the cursor
-    #[allow(clippy::cast_possible_truncation)]
-    write!(
-        out,
-        "{}{}{}{}",
-        MoveTo(0, rows as u16),
-        Clear(ClearType::CurrentLine),
-        if search_mode == SearchMode::Forward {
-            "/"
-        } else {
-            "?"
-        },
-        cursor::Show
-    )?;
-    out.flush()?;
-    let mut string = String::new();
-    loop {
-        if event::poll(Duration::from_millis(10))
-            .map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?
-        {
-            match event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))? {
-                // If Esc is pressed, cancel the search
-                Event::Key(KeyEvent {
-                    code: KeyCode::Esc,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    write!(out, "{}", cursor::Hide)?;
-                    return Ok(String::new());
-                }
-                // On backspace, pop the last character from the string
-                Event::Key(KeyEvent {
-                    code: KeyCode::Backspace,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    string.pop();
-                    // Update the line
-                    write!(out, "\r{}/{}", Clear(ClearType::CurrentLine), string)?;
-                    out.flush()?;
-                }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Enter,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    write!(out, "{}", cursor::Hide)?;
-                    // Return the string when enter is pressed
-                    return Ok(string);
-                }
-                Event::Key(event) => {
-                    // For any character key, without a modifier, append it to the
-                    // string and update the line
-                    if let KeyCode::Char(c) = event.code {
-                        string.push(c);
-                        write!(out, "\r/{}", string)?;
-                        out.flush()?;
+/// How searches treat the case of letters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+#[cfg(feature = "search")]
+#[allow(clippy::module_name_repetitions)]
+pub enum SearchCase {
+    /// Letters only match letters of the same case
+    #[default]
+    Sensitive,
+    /// Letters match letters of any case
+    Insensitive,
+    /// Like [`SearchCase::Insensitive`], unless the query contains an uppercase
+    /// letter
+    Smart,
+}
+
+#[cfg(feature = "search")]
+impl SearchCase {
+    // The mode after this one when the user toggles it
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+            Self::Smart => Self::Sensitive,
+        }
+    }
+}
+
+/// Options that change how search queries match the text
+///
+/// New options may be added in the future without a major version bump, so
+/// this cannot be constructed with a struct literal outside of `minus`. Use
+/// [`SearchOptions::default`] and change the fields instead.
+///
+/// ```
+/// use minus::{Pager, SearchCase, SearchOptions};
+///
+/// let mut options = SearchOptions::default();
+/// options.case = SearchCase::Smart;
+/// options.literal = true;
+///
+/// let mut pager = Pager::new().unwrap();
+/// pager.set_search_options(options);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+#[cfg(feature = "search")]
+#[non_exhaustive]
+#[allow(clippy::module_name_repetitions)]
+pub struct SearchOptions {
+    /// How the case of letters is treated
+    pub case: SearchCase,
+    /// Match the query as plain text instead of as a regular expression
+    pub literal: bool,
+    /// Only match whole words
+    pub whole_word: bool,
+    /// Match lines containing the characters of the query in order, with
+    /// anything in between, like fzf. Whitespace in the query is left out and
+    /// the other options except the case are ignored. See
+    /// [`Pager::set_fuzzy_order`](crate::Pager::set_fuzzy_order) for the order
+    /// matches are visited in.
+    pub fuzzy: bool,
+}
+
+#[cfg(feature = "search")]
+impl SearchOptions {
+    // Whether `query` should match letters of any case
+    fn ignores_case(self, query: &str) -> bool {
+        match self.case {
+            SearchCase::Sensitive => false,
+            SearchCase::Insensitive => true,
+            SearchCase::Smart => {
+                // Escapes like `\W` are not letters to look for
+                let mut chars = query.chars();
+                while let Some(c) = chars.next() {
+                    if c == '\\' && !self.literal && !self.fuzzy {
+                        chars.next();
+                    } else if c.is_uppercase() {
+                        return false;
                     }
                 }
-                _ => continue,
+                true
             }
         }
     }
+
+    // Compile `query` into the regex used for searching
+    //
+    // For a fuzzy query, this matches the text containing it's characters in
+    // order.
+    pub(crate) fn compile(self, query: &str) -> Result<regex::Regex, regex::Error> {
+        let mut pattern = if self.fuzzy {
+            fuzzy::query_chars(query)
+                .map(|c| regex::escape(&c.to_string()))
+                .collect::<Vec<String>>()
+                .join(".*?")
+        } else if self.literal {
+            regex::escape(query)
+        } else {
+            query.to_string()
+        };
+        if self.whole_word && !self.fuzzy {
+            pattern = format!(r"\b(?:{pattern})\b");
+        }
+        regex::RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignores_case(query))
+            .build()
+    }
+
+    // Names of the options that differ from the defaults, shown before the
+    // search prompt like less does
+    pub(crate) fn flags(self) -> String {
+        let mut flags = String::new();
+        match self.case {
+            SearchCase::Sensitive => {}
+            SearchCase::Insensitive => flags.push_str("Ignore-case "),
+            SearchCase::Smart => flags.push_str("Smart-case "),
+        }
+        if self.literal {
+            flags.push_str("Literal ");
+        }
+        if self.whole_word {
+            flags.push_str("Whole-word ");
+        }
+        if self.fuzzy {
+            flags.push_str("Fuzzy ");
+        }
+        flags
+    }
+}
+
+/// The position of a search match in the output
+///
+/// See [`Pager::search_matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+#[cfg(feature = "search")]
+#[non_exhaustive]
+pub struct MatchPosition {
+    /// The number of the line in the whole output, starting from 1
+    pub line: usize,
+    /// The range of bytes of the match in the text of the line, without any
+    /// ANSI escape sequences
+    pub range: Range<usize>,
 }
 
-// Set `Pager.search_idx` to the line numbers at which search matches are found
+// A match of the search in a logical line
+//
+// Matching is done on the text of whole logical lines as it was given, so that
+// matches running over the end of a row are found too. Escape sequences are left
+// out of the text, as they are not seen by the user.
+#[cfg(feature = "search")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SearchMatch {
+    // Index of the logical line among those displayed
+    pub(crate) line: usize,
+    // Byte range of the match in the text of the line without escape sequences
+    pub(crate) range: Range<usize>,
+    // Index of the wrapped row the match starts on
+    pub(crate) row: usize,
+    // How good a fuzzy match is, higher is better. Always 0 for other matches
+    pub(crate) score: i32,
+}
+
+/// The order in which `n` and `p` visit the matches of a fuzzy search
+///
+/// See [`Pager::set_fuzzy_order`](crate::Pager::set_fuzzy_order).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "search")))]
+#[cfg(feature = "search")]
+pub enum FuzzyOrder {
+    /// In the order they appear in the output, like other matches
+    #[default]
+    Document,
+    /// From the best match to the worst, like fzf
+    Score,
+}
+
+// The search term, compiled from a query
+#[cfg(feature = "search")]
+#[derive(Debug, Clone)]
+pub(crate) struct SearchTerm {
+    // Matches the text the search matches in. For a fuzzy search, that is the
+    // text containing the characters of the query in order
+    pub(crate) regex: regex::Regex,
+    pub(crate) fuzzy: Option<Fuzzy>,
+}
+
+#[cfg(feature = "search")]
+impl SearchTerm {
+    pub(crate) fn new(query: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: options.compile(query)?,
+            fuzzy: options
+                .fuzzy
+                .then(|| Fuzzy::new(query, options.ignores_case(query))),
+        })
+    }
+
+    // The matches in `text`, as their byte ranges and scores
+    //
+    // A fuzzy search matches at most once in a text, from the first to the last
+    // character matched. Empty matches cannot be highlighted or told apart, they
+    // are left out.
+    pub(crate) fn find_matches(&self, text: &str) -> Vec<(Range<usize>, i32)> {
+        match &self.fuzzy {
+            Some(fuzzy) => fuzzy
+                .find(text)
+                .and_then(|(score, ranges)| {
+                    Some((ranges.first()?.start..ranges.last()?.end, score))
+                })
+                .into_iter()
+                .collect(),
+            None => self
+                .regex
+                .find_iter(text)
+                .map(|m| (m.range(), 0))
+                .filter(|(range, _)| !range.is_empty())
+                .collect(),
+        }
+    }
+
+    // The byte ranges of `text` to highlight: the matches, or for a fuzzy search
+    // the characters matched
+    pub(crate) fn highlight_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.fuzzy {
+            Some(fuzzy) => fuzzy
+                .find(text)
+                .map(|(_, ranges)| ranges)
+                .unwrap_or_default(),
+            None => self.regex.find_iter(text).map(|m| m.range()).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "search")]
+impl From<regex::Regex> for SearchTerm {
+    fn from(regex: regex::Regex) -> Self {
+        Self { regex, fuzzy: None }
+    }
+}
+
+// Number of logical lines searched for matches at a time
+//
+// Outputs larger than this are searched a chunk at a time between the events,
+// so that the pager can be used while searching millions of lines.
+#[cfg(feature = "search")]
+pub(crate) const INDEX_CHUNK: usize = 50_000;
+
+// What the lines left to search for matches are searched for
+#[cfg(feature = "search")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Indexing {
+    // Keeping the matches of the search up to date, without moving
+    Update,
+    // A new search, which moves to the first match after the top of the screen
+    // as soon as it is found
+    Search,
+    // A new search that moved to it's first match already
+    Found,
+}
+
+// Set `Pager.search_idx` to the matches of the search term, in the order they
+// appear in the text
+//
+// Only the first chunk of lines is searched right away, the event loop searches
+// the rest with `continue_indexing`.
 #[cfg(feature = "search")]
 pub(crate) fn set_match_indices(pager: &mut Pager) {
-    let pattern = pager.search_term.as_ref().unwrap();
-    let mut coordinates: Vec<usize> = Vec::new();
+    pager.search_idx.clear();
+    pager.search_scanned = 0;
+    pager.search_scanned_rows = 0;
+    // A new search still looking for it's first match keeps looking
+    pager.search_indexing = Some(pager.search_indexing.unwrap_or(Indexing::Update));
+    index_chunk(pager);
+}
+
+// Add the matches in the lines added since the matches were last found
+//
+// If the user asked for it with `Pager::set_jump_to_new_matches`, the pager
+// moves to the first new match.
+#[cfg(feature = "search")]
+pub(crate) fn add_new_matches(pager: &mut Pager) {
+    // The lines being searched already include the new ones
+    if pager.search_indexing.is_some() {
+        return;
+    }
+    let found = pager.search_idx.len();
+    if !index_lines(pager, pager.search_chunk) {
+        pager.search_indexing = Some(Indexing::Update);
+    }
+    if pager.jump_to_new_matches && pager.search_idx.len() > found {
+        pager.search_mark = found;
+        scroll_to_match(pager);
+        report_match(pager, MatchMove::Found);
+    }
+}
+
+// Search the next chunk of lines for matches
+//
+// Returns where a new search moved to once all the lines are searched.
+#[cfg(feature = "search")]
+fn index_chunk(pager: &mut Pager) -> Option<MatchMove> {
+    let mut indexing = pager.search_indexing?;
+    let done = index_lines(pager, pager.search_chunk);
+    // The best match is only known once all the lines are searched
+    if indexing == Indexing::Search && !ranked(pager) {
+        next_match(pager);
+        if pager.search_mark < pager.search_idx.len() {
+            indexing = Indexing::Found;
+        }
+    }
+    if !done {
+        pager.search_indexing = Some(indexing);
+        return None;
+    }
+    pager.search_indexing = None;
+    match indexing {
+        Indexing::Update => {
+            pager.search_mark = pager
+                .search_mark
+                .min(pager.search_idx.len().saturating_sub(1));
+            None
+        }
+        Indexing::Found => Some(MatchMove::Found),
+        Indexing::Search if ranked(pager) => Some(match ranking(pager).first() {
+            Some(best) => {
+                pager.search_mark = *best;
+                scroll_to_match(pager);
+                MatchMove::Found
+            }
+            None => MatchMove::NotFound,
+        }),
+        // Without a match after the start, the search wraps around to the top
+        Indexing::Search => Some(match pager.search_idx.first() {
+            Some(_) => {
+                pager.search_mark = 0;
+                scroll_to_match(pager);
+                MatchMove::WrappedToTop
+            }
+            None => MatchMove::NotFound,
+        }),
+    }
+}
 
-    // Get all the lines in wrapping, check if they have a match and put their line numbers if they
-    // do
-    for (idx, line) in pager.get_flattened_lines().enumerate() {
-        if pattern.is_match(&(*line).to_string()) {
-            coordinates.push(idx);
+// Search the next chunk of the lines left to search, if any, and tell the user
+// how far a new search got
+//
+// Returns whether the screen needs to be drawn again.
+#[cfg(feature = "search")]
+pub(crate) fn continue_indexing(pager: &mut Pager) -> bool {
+    if pager.search_term.is_none() {
+        pager.search_indexing = None;
+    }
+    match pager.search_indexing {
+        None => false,
+        Some(Indexing::Update) => {
+            index_chunk(pager);
+            false
         }
+        Some(_) => {
+            match index_chunk(pager) {
+                Some(moved) => report_match(pager, moved),
+                None => report_progress(pager),
+            }
+            true
+        }
+    }
+}
+
+// Tell the user how much of the output a new search went through
+#[cfg(feature = "search")]
+fn report_progress(pager: &mut Pager) {
+    let total = pager.num_logical_lines().max(1);
+    let percent = pager.search_scanned * 100 / total;
+    let found = pager.search_idx.len();
+    pager.send_message(format!(
+        "Searching... {percent}%, {found} found so far. Esc to stop"
+    ));
+}
+
+// If `event` is Esc while lines are left to search, stop searching them and keep
+// the matches found so far
+//
+// Returns whether the event was used up.
+#[cfg(feature = "search")]
+pub(crate) fn stop_indexing(pager: &mut Pager, event: &Event) -> bool {
+    if !matches!(
+        event,
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            ..
+        })
+    ) {
+        return false;
     }
-    pager.search_idx = coordinates;
+    let Some(indexing) = pager.search_indexing.take() else {
+        return false;
+    };
+    // Lines added from now on are still searched
+    pager.search_scanned = pager.num_logical_lines();
+    pager.search_scanned_rows = pager.num_lines();
+    pager.search_mark = pager
+        .search_mark
+        .min(pager.search_idx.len().saturating_sub(1));
+    if indexing != Indexing::Update {
+        pager.send_message(format!(
+            "Search stopped, {} found. Press Enter",
+            pager.search_idx.len()
+        ));
+    }
+    true
+}
+
+// Whether the logical line numbered `line` in the whole output is searched, when
+// searches are limited to the lines in `scope`
+#[cfg(feature = "search")]
+pub(crate) fn in_scope(scope: Option<(usize, usize)>, line: usize) -> bool {
+    scope.map_or(true, |(start, end)| (start..=end).contains(&line))
 }
 
+// Add the matches in at most `count` logical lines displayed after the ones
+// searched already
+//
+// Only the lines searched are looked at, the row they start at is carried over
+// from the previous lines in `Pager.search_scanned_rows`. Returns whether the
+// lines were searched up to the last one.
 #[cfg(feature = "search")]
-pub(crate) fn highlight_line_matches(line: &mut String, query: &regex::Regex) {
-    use crossterm::style::Stylize;
-    // Replace all matches with a reverse colorscheme
-    if let Some(cap) = query.captures(line) {
-        let text = format!("{}{}{}", Attribute::Reverse, &cap[0], Attribute::NoReverse);
-        let text = text.as_str();
-        *line = query.replace_all(line, text).to_string();
+fn index_lines(pager: &mut Pager, count: usize) -> bool {
+    let term = pager.search_term.as_ref().unwrap();
+    let from = pager.search_scanned;
+    let mut to = from;
+    let mut first_row = pager.search_scanned_rows;
+    let mut matches: Vec<SearchMatch> = Vec::new();
+
+    for (idx, line) in (from..).zip(pager.shown_indices(from).take(count)) {
+        let (rows, text) = (&pager.wrap_lines[line], &pager.text_lines[line]);
+        to = idx + 1;
+        if !in_scope(pager.active_search_scope(), pager.original_line(idx + 1)) {
+            first_row += rows.len();
+            continue;
+        }
+        let stripped = Stripped::new(text);
+        let starts = row_starts(text, rows);
+        for (range, score) in term.find_matches(stripped.text()) {
+            let start = stripped.original_range(range.clone()).start;
+            let row_in_line = starts.partition_point(|s| *s <= start) - 1;
+            matches.push(SearchMatch {
+                line: idx,
+                range,
+                row: first_row + row_in_line,
+                score,
+            });
+        }
+        first_row += rows.len();
     }
+    let done = to >= pager.num_logical_lines();
+    pager.search_scanned = to;
+    pager.search_scanned_rows = first_row;
+    pager.search_idx.append(&mut matches);
+    done
+}
+
+// Returns the text of the current search match, if any
+#[cfg(feature = "search")]
+pub(crate) fn match_text(pager: &Pager) -> Option<String> {
+    let found = pager.search_idx.get(pager.search_mark)?;
+    let text = strip_ansi(pager.shown_text(found.line)?);
+    text.get(found.range.clone()).map(ToString::to_string)
+}
+
+// Where moving to a search match ended up
+#[cfg(feature = "search")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MatchMove {
+    // There are no matches
+    NotFound,
+    // Moved to the match asked for
+    Found,
+    // There was no match after the current one, moved to the first match
+    WrappedToTop,
+    // There was no match before the current one, moved to the last match
+    WrappedToBottom,
+}
+
+// Search for `pattern` and move to the first match at or after the row `start`,
+// or to the very first match if there is none after it
+//
+// Returns where the pager moved to, or `None` if there are lines left to search.
+// In that case the pager moves to the first match after the start as soon as
+// it is found.
+#[cfg(feature = "search")]
+pub(crate) fn search_from(
+    pager: &mut Pager,
+    term: impl Into<SearchTerm>,
+    start: usize,
+) -> Option<MatchMove> {
+    pager.search_term = Some(term.into());
+    pager.search_idx.clear();
+    pager.search_scanned = 0;
+    pager.search_scanned_rows = 0;
+    pager.search_mark = 0;
+    pager.upper_mark = start;
+    pager.search_indexing = Some(Indexing::Search);
+    index_chunk(pager)
+}
+
+// Show the lines matching the search in the occur view, a temporary buffer
+// listing each of them with it's line number in the whole output, and focus the
+// first one at or after the top of the screen
+//
+// Tells the user instead if there is nothing to list.
+#[cfg(feature = "search")]
+pub(crate) fn show_occur(pager: &mut Pager) {
+    if pager.search_term.is_none() {
+        pager.send_message("No search to list the matches of. Press Enter");
+        return;
+    }
+    // The list has to be complete, so the lines left are searched right away
+    while pager.search_indexing.is_some() {
+        index_chunk(pager);
+    }
+    let mut lines: Vec<usize> = pager.search_idx.iter().map(|m| m.line).collect();
+    lines.dedup();
+    if lines.is_empty() {
+        pager.send_message("Pattern not found. Press Enter");
+        return;
+    }
+    let numbers: Vec<usize> = lines.iter().map(|l| pager.original_line(l + 1)).collect();
+    let width = numbers.last().map_or(1, |n| n.to_string().len());
+    let text = lines
+        .iter()
+        .zip(&numbers)
+        .filter_map(|(line, number)| {
+            let text = pager.shown_text(*line)?;
+            Some(format!("{number:>width$}: {text}"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let query = pager
+        .search_query
+        .clone()
+        .unwrap_or_else(|| pager.search_term.as_ref().unwrap().regex.to_string());
+    let prompt = format!(
+        "{} lines matching {query}, Enter to go to one, q to return",
+        lines.len()
+    );
+    let top = pager.line_of_row(pager.upper_mark);
+    let focused = lines.partition_point(|line| line + 1 < top) + 1;
+    if buffer::show(pager, &text, &prompt) {
+        if let Some(saved) = pager.saved_content.as_mut() {
+            saved.occur = Some(numbers);
+        }
+        let focused = focused.min(lines.len());
+        pager.focused_line = Some(focused);
+        pager.upper_mark = pager.row_of_line(focused);
+    }
+}
+
+// Leave the occur view for the output, at the line the user is at in the list.
+// The first match on that line becomes the current match
+//
+// Returns false if the occur view is not shown.
+#[cfg(feature = "search")]
+pub(crate) fn go_to_occurrence(pager: &mut Pager) -> bool {
+    let Some(occur) = pager.saved_content.as_ref().and_then(|s| s.occur.as_ref()) else {
+        return false;
+    };
+    let Some(&line) = occur.get(pager.cursor_line() - 1) else {
+        return false;
+    };
+    buffer::restore(pager);
+    let shown = pager.shown_line(line);
+    pager.upper_mark = pager.row_of_line(shown);
+    pager.focused_line = Some(shown);
+    if let Some(mark) = pager.search_idx.iter().position(|m| m.line + 1 == shown) {
+        pager.search_mark = mark;
+    }
+    true
+}
+
+// Whether the current match is on the screen
+#[cfg(feature = "search")]
+fn current_match_visible(pager: &Pager) -> bool {
+    pager.search_idx.get(pager.search_mark).is_some_and(|m| {
+        m.row >= pager.upper_mark && m.row < pager.upper_mark + pager.rows.saturating_sub(1)
+    })
+}
+
+// Number of rows kept above the current match by default
+pub(crate) const MATCH_CONTEXT: usize = 3;
+
+// Scroll to the current match, leaving `Pager.match_context` rows above it as
+// far as the screen allows
+#[cfg(feature = "search")]
+fn scroll_to_match(pager: &mut Pager) {
+    if let Some(m) = pager.search_idx.get(pager.search_mark) {
+        let context = pager.match_context.min(pager.rows.saturating_sub(2));
+        pager.upper_mark = m.row.saturating_sub(context);
+    }
+}
+
+// Whether the matches are visited from the best to the worst instead of in the
+// order they appear
+#[cfg(feature = "search")]
+fn ranked(pager: &Pager) -> bool {
+    pager.fuzzy_order == FuzzyOrder::Score
+        && pager
+            .search_term
+            .as_ref()
+            .is_some_and(|term| term.fuzzy.is_some())
+}
+
+// Indices in `Pager.search_idx` of the matches from the best to the worst.
+// Matches that score the same stay in the order they appear
+#[cfg(feature = "search")]
+fn ranking(pager: &Pager) -> Vec<usize> {
+    let mut ranking: Vec<usize> = (0..pager.search_idx.len()).collect();
+    ranking.sort_by_key(|idx| std::cmp::Reverse(pager.search_idx[*idx].score));
+    ranking
+}
+
+// Move to the match ranked after the current one if `next` is true, or before
+// it otherwise. Wraps around past the worst and the best match
+#[cfg(feature = "search")]
+fn go_to_ranked_match(pager: &mut Pager, next: bool) -> MatchMove {
+    let ranking = ranking(pager);
+    let rank = ranking
+        .iter()
+        .position(|idx| *idx == pager.search_mark)
+        .unwrap_or(0);
+    let (rank, moved) = if next {
+        match rank + 1 {
+            next if next < ranking.len() => (next, MatchMove::Found),
+            _ => (0, MatchMove::WrappedToTop),
+        }
+    } else {
+        match rank.checked_sub(1) {
+            Some(prev) => (prev, MatchMove::Found),
+            None => (ranking.len() - 1, MatchMove::WrappedToBottom),
+        }
+    };
+    pager.search_mark = ranking[rank];
+    scroll_to_match(pager);
+    moved
+}
+
+// Move to the match after the current one, or to the first match after the
+// top of the screen if that match is not on the screen anymore. Wraps around to
+// the first match after the last one
+//
+// The matches of a fuzzy search may be visited by score instead, see
+// `Pager::set_fuzzy_order`.
+#[cfg(feature = "search")]
+pub(crate) fn go_to_next_match(pager: &mut Pager) -> MatchMove {
+    if pager.search_idx.is_empty() {
+        return MatchMove::NotFound;
+    }
+    if ranked(pager) {
+        return go_to_ranked_match(pager, true);
+    }
+    let next = if current_match_visible(pager) {
+        pager.search_mark + 1
+    } else {
+        let upper_mark = pager.upper_mark;
+        pager.search_idx.partition_point(|m| m.row <= upper_mark)
+    };
+    let moved = if next < pager.search_idx.len() {
+        pager.search_mark = next;
+        MatchMove::Found
+    } else {
+        pager.search_mark = 0;
+        MatchMove::WrappedToTop
+    };
+    scroll_to_match(pager);
+    moved
+}
+
+// Move to the match before the current one, or to the last match before the
+// top of the screen if that match is not on the screen anymore. Wraps around to
+// the last match before the first one
+#[cfg(feature = "search")]
+pub(crate) fn go_to_prev_match(pager: &mut Pager) -> MatchMove {
+    if pager.search_idx.is_empty() {
+        return MatchMove::NotFound;
+    }
+    if ranked(pager) {
+        return go_to_ranked_match(pager, false);
+    }
+    let prev = if current_match_visible(pager) {
+        pager.search_mark.checked_sub(1)
+    } else {
+        let upper_mark = pager.upper_mark;
+        pager
+            .search_idx
+            .partition_point(|m| m.row < upper_mark)
+            .checked_sub(1)
+    };
+    let moved = if let Some(prev) = prev {
+        pager.search_mark = prev;
+        MatchMove::Found
+    } else {
+        pager.search_mark = pager.search_idx.len() - 1;
+        MatchMove::WrappedToBottom
+    };
+    scroll_to_match(pager);
+    moved
+}
+
+// Tell the user which match they are at after moving to it
+#[cfg(feature = "search")]
+pub(crate) fn report_match(pager: &mut Pager, moved: MatchMove) {
+    let total = pager.search_idx.len();
+    // Ranked matches are numbered from the best one
+    let current = if ranked(pager) {
+        ranking(pager)
+            .iter()
+            .position(|idx| *idx == pager.search_mark)
+            .map_or(0, |rank| rank + 1)
+    } else {
+        (pager.search_mark + 1).min(total)
+    };
+    let message = match moved {
+        MatchMove::NotFound => match pager.active_search_scope() {
+            Some((start, end)) => format!("Pattern not found in lines {start}-{end}. Press Enter"),
+            None => "Pattern not found. Press Enter".to_string(),
+        },
+        MatchMove::Found => format!("Match {current} of {total}"),
+        MatchMove::WrappedToTop if ranked(pager) => {
+            format!("Match {current} of {total}, passed the worst match, back to the best")
+        }
+        MatchMove::WrappedToBottom if ranked(pager) => {
+            format!("Match {current} of {total}, passed the best match, back to the worst")
+        }
+        MatchMove::WrappedToTop => {
+            format!("Match {current} of {total}, search hit BOTTOM, continuing at TOP")
+        }
+        MatchMove::WrappedToBottom => {
+            format!("Match {current} of {total}, search hit TOP, continuing at BOTTOM")
+        }
+    };
+    pager.send_message(message);
 }
 
 // Set variables to move to the next match
 #[cfg(feature = "search")]
-pub(crate) fn next_match(pager: &mut Pager, s_mark: &mut usize) {
+pub(crate) fn next_match(pager: &mut Pager) {
     // Loop untill we find a match, that's below the upper_mark
     //
     // Get match at the given mark
-    while let Some(y) = pager.search_idx.get(*s_mark) {
+    while let Some(m) = pager.search_idx.get(pager.search_mark) {
         // If it's above upper_mark, continue for the next match
-        if *y < pager.upper_mark {
-            *s_mark += 1;
+        if m.row < pager.upper_mark {
+            pager.search_mark += 1;
         } else {
             // If the condition is satisfied, set it and break
-            pager.upper_mark = *y as usize;
             break;
         }
     }
+    scroll_to_match(pager);
+}
+
+// Search for `query` from the row `start` and tell the user which match the
+// pager moved to, like a query entered at the prompt
+//
+// The query is added to the history even if it is invalid, so that the user can
+// correct it. The search is left as it was if the query is invalid.
+#[cfg(feature = "search")]
+pub(crate) fn run_search(
+    pager: &mut Pager,
+    query: String,
+    mode: SearchMode,
+    options: SearchOptions,
+    start: usize,
+) -> Result<(), regex::Error> {
+    pager.search_history.add(mode, query.clone());
+    // Failing to save the history is not worth interrupting the user for,
+    // the queries are still kept for this session
+    let _ = pager.search_history.save();
+    let term = SearchTerm::new(&query, options)?;
+    pager.search_mode = mode;
+    pager.search_query = Some(query);
+    match search_from(pager, term, start) {
+        Some(moved) => report_match(pager, moved),
+        None => report_progress(pager),
+    }
+    Ok(())
+}
+
+// Move to the next match in the direction of the search like `n` if `next` is
+// true, or to the previous one like `p`, and tell the user which match the pager
+// moved to
+#[cfg(feature = "search")]
+pub(crate) fn go_to_match(pager: &mut Pager, next: bool) {
+    let moved = if next == (pager.search_mode == SearchMode::Reverse) {
+        go_to_prev_match(pager)
+    } else {
+        go_to_next_match(pager)
+    };
+    report_match(pager, moved);
+}
+
+// The prompt for a search query
+//
+// If incremental search is on, the query is searched for while it is typed.
+// Inside the prompt, `Ctrl+R` toggles matching the query literally, `Alt+C`
+// cycles through the case modes, `Alt+W` toggles matching whole words and
+// `Ctrl+F` fuzzy matching. These only apply to this query. They are used
+// before the editing keys, so `Ctrl+F` does not move the cursor here.
+#[cfg(feature = "search")]
+pub(crate) struct SearchPrompt<'a> {
+    pager: &'a mut Pager,
+    mode: SearchMode,
+    options: SearchOptions,
+    history: Vec<String>,
+    // The row the search started from
+    start: usize,
+    // The search as it was before the prompt, restored if it is cancelled
+    previous_term: Option<SearchTerm>,
+    previous_mark: usize,
+}
+
+#[cfg(feature = "search")]
+impl<'a> SearchPrompt<'a> {
+    pub(crate) fn new(pager: &'a mut Pager, mode: SearchMode) -> Self {
+        Self {
+            mode,
+            options: pager.search_options,
+            history: pager.search_history.entries(mode).to_vec(),
+            start: pager.upper_mark,
+            previous_term: pager.search_term.clone(),
+            previous_mark: pager.search_mark,
+            pager,
+        }
+    }
+
+    // Put the search and the position back as they were before the prompt
+    pub(crate) fn cancel(mut self) {
+        self.restore();
+    }
+
+    fn restore(&mut self) {
+        self.pager.search_term.clone_from(&self.previous_term);
+        self.pager.search_indexing = None;
+        self.pager.search_mark = self.previous_mark;
+        // The matches may not all have been found before the prompt, find them
+        // again instead of keeping them
+        if self.pager.search_term.is_some() {
+            set_match_indices(self.pager);
+        } else {
+            self.pager.search_idx.clear();
+        }
+        self.pager.upper_mark = self.start;
+    }
+
+    // Search for the `query` entered by the user
+    pub(crate) fn finish(self, query: String) {
+        if run_search(self.pager, query, self.mode, self.options, self.start).is_err() {
+            // Send invalid regex message at the prompt if invalid regex is given
+            self.pager
+                .send_message("Invalid regular expression. Press Enter");
+            self.cancel();
+        }
+    }
+}
+
+#[cfg(feature = "search")]
+impl<O: std::io::Write> PromptHandler<O> for SearchPrompt<'_> {
+    fn prompt(&self) -> String {
+        let symbol = if self.mode == SearchMode::Reverse {
+            '?'
+        } else {
+            '/'
+        };
+        let scope = self
+            .pager
+            .active_search_scope()
+            .map_or_else(String::new, |(start, end)| format!("Lines {start}-{end} "));
+        format!("{}{scope}{symbol}", self.options.flags())
+    }
+
+    fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.options.literal = !self.options.literal;
+            }
+            (KeyCode::Char('c'), KeyModifiers::ALT) => self.options.case = self.options.case.next(),
+            (KeyCode::Char('w'), KeyModifiers::ALT) => {
+                self.options.whole_word = !self.options.whole_word;
+            }
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => self.options.fuzzy = !self.options.fuzzy,
+            _ => return false,
+        }
+        true
+    }
+
+    fn on_change(&mut self, out: &mut O, query: &str) -> Result<(), AlternateScreenPagingError> {
+        if !self.pager.incremental_search {
+            return Ok(());
+        }
+        if query.is_empty() {
+            self.restore();
+        } else if let Ok(term) = SearchTerm::new(query, self.options) {
+            search_from(self.pager, term, self.start);
+        } else {
+            // The query is likely being typed, keep showing the matches of the
+            // last valid one
+            return Ok(());
+        }
+        draw(out, self.pager)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{highlight_line_matches, next_match, set_match_indices};
+    use super::{
+        continue_indexing, go_to_next_match, go_to_prev_match, match_text, next_match,
+        report_match, search_from, set_match_indices, show_occur, stop_indexing, MatchMove,
+        SearchCase, SearchMatch, SearchOptions,
+    };
     use crate::Pager;
-    use crossterm::style::Attribute;
     use regex::Regex;
 
+    // The rows the search matches start on
+    fn match_rows(pager: &Pager) -> Vec<usize> {
+        pager.search_idx.iter().map(|m| m.row).collect()
+    }
+
     #[test]
     fn test_next_match() {
         let mut pager = Pager::new().unwrap();
-        let mut s_mark = 0;
+        pager.set_match_context(0);
+        pager.search_mark = 0;
         // A sample index for mocking actual search index matches
-        pager.search_idx = vec![2, 10, 15, 17, 50];
-        for i in &pager.search_idx.clone() {
-            next_match(&mut pager, &mut s_mark);
+        pager.search_idx = [2, 10, 15, 17, 50]
+            .iter()
+            .map(|row| SearchMatch {
+                line: *row,
+                range: 0..1,
+                row: *row,
+                score: 0,
+            })
+            .collect();
+        for i in &match_rows(&pager) {
+            next_match(&mut pager);
             dbg!(pager.upper_mark);
-            assert_eq!(pager.upper_mark, *i as usize);
-            s_mark += 1;
+            assert_eq!(pager.upper_mark, *i);
+            pager.search_mark += 1;
         }
     }
 
     #[test]
-    fn test_highlight_matches() {
-        let mut line = "Integer placerat tristique nisl. placerat non mollis, magna orci dolor, placerat at vulputate neque nulla lacinia eros.".to_string();
-        let pat = Regex::new(r"\W\w+t\W").unwrap();
-        let result = format!(
-            "Integer{inverse} placerat {noinverse}tristique nisl.\
-{inverse} placerat {noinverse}non mollis, magna orci dolor,\
-{inverse} placerat {noinverse}at vulputate neque nulla lacinia \
-eros.",
-            inverse = Attribute::Reverse,
-            noinverse = Attribute::NoReverse
+    fn test_prompt_keys() {
+        use super::SearchPrompt;
+        use crate::{
+            utils::{
+                line_editor::LineEditor,
+                prompt::{edit, Edit, PromptHandler},
+            },
+            SearchMode,
+        };
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut pager = Pager::new().unwrap();
+        let mut prompt = SearchPrompt::new(&mut pager, SearchMode::Forward);
+        let mut editor = LineEditor::new();
+        let ctrl_f = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL);
+        // Ctrl+F toggles fuzzy matching instead of moving the cursor
+        assert_eq!(
+            edit::<Vec<u8>>(&mut editor, &mut prompt, ctrl_f),
+            Edit::Prompt
+        );
+        assert!(prompt.options.fuzzy);
+        assert_eq!(PromptHandler::<Vec<u8>>::prompt(&prompt), "Fuzzy /");
+        assert_eq!(
+            edit::<Vec<u8>>(&mut editor, &mut prompt, ctrl_f),
+            Edit::Prompt
         );
+        assert!(!prompt.options.fuzzy);
+        // Other keys still edit the query
+        let key = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        assert_eq!(edit::<Vec<u8>>(&mut editor, &mut prompt, key), Edit::Text);
+        assert_eq!(editor.text(), "a");
+    }
+
+    #[test]
+    fn test_search_options() {
+        let compile = |options: SearchOptions, query| options.compile(query).unwrap();
+        let mut options = SearchOptions::default();
+        assert!(!compile(options, "foo").is_match("Foo"));
+
+        options.case = SearchCase::Insensitive;
+        assert!(compile(options, "Foo").is_match("FOO"));
 
-        highlight_line_matches(&mut line, &pat);
-        assert_eq!(line, result);
+        // Smart case ignores the case unless the query has an uppercase letter
+        options.case = SearchCase::Smart;
+        assert!(compile(options, "foo").is_match("FOO"));
+        assert!(!compile(options, "Foo").is_match("FOO"));
+        // Escapes do not count as uppercase letters, unless they are searched
+        // literally
+        assert!(compile(options, r"foo\W").is_match("FOO!"));
+        options.literal = true;
+        assert!(!compile(options, r"foo\W").is_match(r"FOO\w"));
+
+        // Literal queries are not regexes
+        options.case = SearchCase::Sensitive;
+        assert!(compile(options, "foo(bar)").is_match("call foo(bar)"));
+        assert!(!compile(options, "a.b").is_match("axb"));
+        options.literal = false;
+        assert!(options.compile("foo(bar").is_err());
+
+        // Whole words only
+        options.whole_word = true;
+        assert!(compile(options, "cat|dog").is_match("a dog here"));
+        assert!(!compile(options, "cat|dog").is_match("concatenate dogs"));
+
+        assert_eq!(options.flags(), "Whole-word ");
+        options.case = SearchCase::Smart;
+        options.literal = true;
+        assert_eq!(options.flags(), "Smart-case Literal Whole-word ");
+    }
+
+    #[test]
+    fn test_search_from() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_match_context(0);
+        let lines: Vec<String> = (0..30).map(|i| format!("line {}", i % 10)).collect();
+        pager.set_text(lines.join("\n"));
+        pager.search_mark = 4;
+
+        // The first match at or after the start is chosen
+        search_from(&mut pager, Regex::new("line 3").unwrap(), 5);
+        assert_eq!(match_rows(&pager), [3, 13, 23]);
+        assert_eq!(pager.upper_mark, 13);
+        assert_eq!(pager.search_mark, 1);
+
+        // Searching again starts over from the given position, not from the
+        // last match
+        search_from(&mut pager, Regex::new("line 4").unwrap(), 5);
+        assert_eq!(pager.upper_mark, 14);
+
+        // Without a match after the start, the search wraps around to the top
+        let moved = search_from(&mut pager, Regex::new("line 1").unwrap(), 25);
+        assert_eq!(moved, Some(MatchMove::WrappedToTop));
+        assert_eq!((pager.upper_mark, pager.search_mark), (1, 0));
+
+        let moved = search_from(&mut pager, Regex::new("nothing").unwrap(), 25);
+        assert_eq!(moved, Some(MatchMove::NotFound));
+        assert_eq!(pager.upper_mark, 25);
+    }
+
+    #[test]
+    fn test_search_in_chunks() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_match_context(0);
+        let lines: Vec<String> = (0..30).map(|i| format!("line {}", i % 10)).collect();
+        pager.set_text(lines.join("\n"));
+        pager.search_chunk = 4;
+
+        // Only the first chunk is searched right away
+        assert_eq!(
+            search_from(&mut pager, Regex::new("line 3").unwrap(), 5),
+            None
+        );
+        assert_eq!(match_rows(&pager), [3]);
+        assert_eq!(pager.upper_mark, 5);
+
+        // The pager moves to the first match after the start once it is found,
+        // and tells how far the search got
+        for _ in 0..3 {
+            assert!(continue_indexing(&mut pager));
+        }
+        assert_eq!(pager.upper_mark, 13);
+        assert_eq!(
+            pager.message.0.as_ref().unwrap()[0],
+            "Searching... 53%, 2 found so far. Esc to stop"
+        );
+        while pager.search_indexing.is_some() {
+            continue_indexing(&mut pager);
+        }
+        assert_eq!(match_rows(&pager), [3, 13, 23]);
+        assert_eq!(pager.message.0.as_ref().unwrap()[0], "Match 2 of 3");
+        assert!(!continue_indexing(&mut pager));
+
+        // Each chunk starts at the row the previous one ended at
+        pager.cols = 4;
+        pager.readjust_wraps();
+        assert_eq!(pager.search_scanned_rows, 8);
+        while pager.search_indexing.is_some() {
+            continue_indexing(&mut pager);
+        }
+        assert_eq!(match_rows(&pager), [6, 26, 46]);
+        assert_eq!(pager.search_scanned_rows, pager.num_lines());
+    }
+
+    #[test]
+    fn test_stop_indexing() {
+        use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+        let mut pager = Pager::new().unwrap();
+        let lines: Vec<String> = (0..30).map(|i| format!("line {}", i % 10)).collect();
+        pager.set_text(lines.join("\n"));
+        pager.search_chunk = 10;
+        search_from(&mut pager, Regex::new("line 3").unwrap(), 0);
+
+        let esc = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        let other = Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert!(!stop_indexing(&mut pager, &other));
+        assert!(stop_indexing(&mut pager, &esc));
+        assert!(pager.search_indexing.is_none());
+        assert_eq!(
+            pager.message.0.as_ref().unwrap()[0],
+            "Search stopped, 1 found. Press Enter"
+        );
+        // Esc is left to the input handler when nothing is being searched
+        assert!(!stop_indexing(&mut pager, &esc));
+
+        // Only the lines added afterwards are searched
+        pager.push_str("\nline 3\n");
+        assert_eq!(pager.search_idx.len(), 2);
+        assert_eq!(pager.search_idx[1].row, 31);
+    }
+
+    #[test]
+    fn test_match_navigation() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_match_context(0);
+        let lines: Vec<String> = (0..100).map(|i| format!("line {i}")).collect();
+        pager.set_text(lines.join("\n"));
+        pager.search_mark = 0;
+        let moved = search_from(&mut pager, Regex::new("0$").unwrap(), 0);
+        assert_eq!(moved, Some(MatchMove::Found));
+        assert_eq!(pager.upper_mark, 0);
+
+        assert_eq!(go_to_next_match(&mut pager), MatchMove::Found);
+        assert_eq!((pager.upper_mark, pager.search_mark), (10, 1));
+        assert_eq!(go_to_prev_match(&mut pager), MatchMove::Found);
+        assert_eq!((pager.upper_mark, pager.search_mark), (0, 0));
+        // Going back from the first match wraps around to the last one
+        assert_eq!(go_to_prev_match(&mut pager), MatchMove::WrappedToBottom);
+        assert_eq!((pager.upper_mark, pager.search_mark), (90, 9));
+        report_match(&mut pager, MatchMove::WrappedToBottom);
+        assert_eq!(
+            pager.message.0.as_ref().unwrap()[0],
+            "Match 10 of 10, search hit TOP, continuing at BOTTOM"
+        );
+        // And forward from the last one to the first
+        assert_eq!(go_to_next_match(&mut pager), MatchMove::WrappedToTop);
+        assert_eq!((pager.upper_mark, pager.search_mark), (0, 0));
+
+        // After scrolling away from the current match, the matches around the
+        // screen are used
+        pager.upper_mark = 43;
+        assert_eq!(go_to_next_match(&mut pager), MatchMove::Found);
+        assert_eq!((pager.upper_mark, pager.search_mark), (50, 5));
+        pager.upper_mark = 43;
+        assert_eq!(go_to_prev_match(&mut pager), MatchMove::Found);
+        assert_eq!((pager.upper_mark, pager.search_mark), (40, 4));
+        report_match(&mut pager, MatchMove::Found);
+        assert_eq!(pager.message.0.as_ref().unwrap()[0], "Match 5 of 10");
+
+        pager.search_idx.clear();
+        assert_eq!(go_to_next_match(&mut pager), MatchMove::NotFound);
+        report_match(&mut pager, MatchMove::NotFound);
+        assert_eq!(
+            pager.message.0.as_ref().unwrap()[0],
+            "Pattern not found. Press Enter"
+        );
+    }
+
+    #[test]
+    fn test_match_text() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_text("first line\nsecond line 42\nthird line 7");
+        pager.search_term = Some(Regex::new(r"\d+").unwrap().into());
+        set_match_indices(&mut pager);
+        pager.search_mark = 0;
+        assert_eq!(match_text(&pager), Some("42".to_string()));
+        pager.search_mark = 1;
+        assert_eq!(match_text(&pager), Some("7".to_string()));
+        pager.search_mark = 2;
+        assert_eq!(match_text(&pager), None);
     }
 
     #[test]
@@ -192,9 +1232,97 @@ orci ultrices dolor, at vulputate neque nulla lacinia eros.  Sed id ligula quis
 convallis tempor.  Curabitur lacinia pulvinar nibh.  Nam a sapien.",
         );
 
-        pager.search_term = Some(Regex::new(r"\Wa\w+\W").unwrap());
-        let res = vec![3, 7, 11];
+        pager.search_term = Some(Regex::new(r"\Wa\w+\W").unwrap().into());
+        // Every match is kept, even several on the same row
+        let res = vec![3, 3, 3, 7, 7, 11];
+        set_match_indices(&mut pager);
+        assert_eq!(match_rows(&pager), res);
+    }
+
+    #[test]
+    fn test_match_across_rows() {
+        let mut pager = Pager::new().unwrap();
+        // The first line is wrapped before `hello`
+        pager.set_text(format!("{} hello world\nhello", "x".repeat(75)));
+        assert_eq!(pager.wrap_lines[0].len(), 2);
+
+        pager.search_term = Some(Regex::new("x hello").unwrap().into());
+        set_match_indices(&mut pager);
+        assert_eq!(match_rows(&pager), [0]);
+        pager.search_mark = 0;
+        assert_eq!(match_text(&pager), Some("x hello".to_string()));
+
+        // Matches are found at the start of wrapped rows and of following lines
+        pager.search_term = Some(Regex::new("hello").unwrap().into());
         set_match_indices(&mut pager);
-        assert_eq!(pager.search_idx, res);
+        assert_eq!(match_rows(&pager), [1, 2]);
+        assert_eq!(pager.search_idx[1].line, 1);
+    }
+
+    #[test]
+    fn test_match_wrapped_text() {
+        let mut pager = Pager::new().unwrap();
+        // The first line is wrapped inside a word, the second at a run of spaces
+        let first = format!("{}needle and needle", "x".repeat(78));
+        let second = format!("{}  tail", "y".repeat(78));
+        pager.set_text(format!("{first}\n{second}"));
+        assert_eq!(pager.wrap_lines[0][1], "edle and needle");
+        assert_eq!(pager.wrap_lines[1], ["y".repeat(78), "tail".to_string()]);
+
+        pager.search_term = Some(Regex::new("needle").unwrap().into());
+        set_match_indices(&mut pager);
+        assert_eq!(match_rows(&pager), [0, 1]);
+        let ranges: Vec<_> = pager.search_idx.iter().map(|m| m.range.clone()).collect();
+        assert_eq!(ranges, [78..84, 89..95]);
+
+        pager.search_term = Some(Regex::new("y  t").unwrap().into());
+        set_match_indices(&mut pager);
+        assert_eq!(match_rows(&pager), [2]);
+        assert_eq!(pager.search_idx[0].range, 77..81);
+        pager.search_mark = 0;
+        assert_eq!(match_text(&pager), Some("y  t".to_string()));
+        // The occur view lists the line with the text it was given
+        show_occur(&mut pager);
+        assert_eq!(pager.text_lines, [format!("2: {second}")]);
+    }
+
+    #[test]
+    fn test_match_styled_text() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_text("plain\n\x1b[1;31merror\x1b[0m: disk full");
+        pager.search_term = Some(Regex::new("error: disk").unwrap().into());
+        set_match_indices(&mut pager);
+        assert_eq!(match_rows(&pager), [1]);
+        assert_eq!(pager.search_idx[0].range, 0..11);
+        pager.search_mark = 0;
+        assert_eq!(match_text(&pager), Some("error: disk".to_string()));
+
+        // Escape sequences are never matched
+        pager.search_term = Some(Regex::new(r"\[|31").unwrap().into());
+        set_match_indices(&mut pager);
+        assert!(pager.search_idx.is_empty());
+    }
+
+    #[test]
+    fn test_matches_on_one_line() {
+        let mut pager = Pager::new().unwrap();
+        pager.set_match_context(0);
+        pager.set_text("one\ncat dog cat dog cat\nthree");
+        pager.search_mark = 0;
+        search_from(&mut pager, Regex::new("cat").unwrap(), 0);
+        assert_eq!(pager.search_idx.len(), 3);
+        assert_eq!((pager.upper_mark, pager.search_mark), (1, 0));
+
+        // Moving to the next match steps through the matches of the line
+        for next in 1..3 {
+            assert_eq!(go_to_next_match(&mut pager), MatchMove::Found);
+            assert_eq!((pager.upper_mark, pager.search_mark), (1, next));
+            assert_eq!(
+                pager.search_idx[pager.search_mark].range,
+                next * 8..next * 8 + 3
+            );
+        }
+        assert_eq!(go_to_prev_match(&mut pager), MatchMove::Found);
+        assert_eq!(pager.search_mark, 1);
     }
 }
@@ -115,3 +115,494 @@ fn test_rewrap() {
     // No change, since it's already in a good optimal state
     assert_eq!((80, 80, 40), (line[0].len(), line[1].len(), line[2].len()));
 }
+
+#[test]
+fn test_row_of_line() {
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    // The second line wraps into two rows
+    pager.set_text("one\ntwo three four\nfive\nsix");
+    assert_eq!(pager.row_of_line(1), 0);
+    assert_eq!(pager.row_of_line(2), 1);
+    assert_eq!(pager.row_of_line(3), 3);
+    // Out of range lines are clamped
+    assert_eq!(pager.row_of_line(0), 0);
+    assert_eq!(pager.row_of_line(100), 4);
+}
+
+#[test]
+fn test_line_of_row() {
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    pager.set_text("one\ntwo three four\nfive\nsix");
+    assert_eq!(pager.line_of_row(0), 1);
+    assert_eq!(pager.line_of_row(1), 2);
+    assert_eq!(pager.line_of_row(2), 2);
+    assert_eq!(pager.line_of_row(3), 3);
+    // Rows past the end belong to the last line
+    assert_eq!(pager.line_of_row(100), 4);
+}
+
+#[test]
+fn test_line_at_percent() {
+    let mut pager = Pager::new().unwrap();
+    for i in 1..=200 {
+        writeln!(pager, "{i}").unwrap();
+    }
+    assert_eq!(pager.line_at_percent(0), 1);
+    assert_eq!(pager.line_at_percent(30), 60);
+    assert_eq!(pager.line_at_percent(100), 200);
+    assert_eq!(pager.line_at_percent(150), 200);
+}
+
+#[test]
+fn test_custom_action() {
+    use crate::input::CustomActionResult;
+
+    let mut pager = Pager::new().unwrap();
+    pager.set_text("one\ntwo");
+    pager.add_custom_action("count", |pager| {
+        CustomActionResult::Message(format!("{} lines", pager.num_logical_lines()))
+    });
+    assert_eq!(
+        pager.run_custom_action("count"),
+        Some(CustomActionResult::Message("2 lines".to_string()))
+    );
+    // The action stays registered after running
+    assert!(pager.custom_actions.contains_key("count"));
+    assert_eq!(pager.run_custom_action("missing"), None);
+}
+
+#[test]
+fn test_selected_text() {
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    pager.set_text("one\ntwo three four\n\x1b[1mfive\x1b[0m\nsix");
+    assert_eq!(pager.selected_text(), None);
+    // Selections can go upwards too
+    pager.selection = Some((3, 2));
+    assert_eq!(
+        pager.selected_text(),
+        Some("two three four\nfive".to_string())
+    );
+}
+
+#[test]
+fn test_cursor_line() {
+    let mut pager = Pager::new().unwrap();
+    pager.rows = 3;
+    pager.set_text("one\ntwo\nthree\nfour\nfive");
+    pager.upper_mark = 1;
+    assert_eq!(pager.cursor_line(), 2);
+    pager.focused_line = Some(3);
+    assert_eq!(pager.cursor_line(), 3);
+    // The focused line is not used once it is scrolled off the screen
+    pager.upper_mark = 3;
+    assert_eq!(pager.cursor_line(), 4);
+}
+
+#[test]
+fn test_text_of_lines() {
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    pager.rows = 4;
+    pager.set_text("one\ntwo three four\nfive\nsix");
+    assert_eq!(pager.text_of_lines(1, 1), "one");
+    assert_eq!(pager.text_of_lines(4, 2), "two three four\nfive\nsix");
+    // Lines are copied with the text they were given, not joined from their rows
+    pager.set_text("https://example.com/x\nwide words  here");
+    assert_eq!(pager.wrap_lines[1], ["wide words", "here"]);
+    assert_eq!(
+        pager.text_of_lines(1, 2),
+        "https://example.com/x\nwide words  here"
+    );
+    pager.set_text("one\ntwo three four\nfive\nsix");
+    // The screen shows three rows, the first two of which belong to the second line
+    pager.upper_mark = 1;
+    assert_eq!(pager.visible_lines(), (2, 3));
+}
+
+#[test]
+fn test_temporary_buffer() {
+    use crate::buffer;
+
+    let mut pager = Pager::new().unwrap();
+    for i in 1..=30 {
+        writeln!(pager, "{i}").unwrap();
+    }
+    pager.upper_mark = 5;
+    pager.marks.insert('a', 3);
+    assert!(buffer::show(&mut pager, "first\nsecond", "Temporary"));
+    assert_eq!(pager.upper_mark, 0);
+    assert_eq!(pager.wrap_lines, vec![vec!["first"], vec!["second"]]);
+    assert_eq!(pager.prompt, vec!["Temporary"]);
+    assert!(pager.marks.is_empty());
+    // Only one temporary buffer can be shown at a time
+    assert!(!buffer::show(&mut pager, "third", "Other"));
+
+    // Data sent in the meantime goes to the output
+    writeln!(pager, "31").unwrap();
+    pager.set_prompt("New prompt");
+    assert_eq!(pager.num_logical_lines(), 2);
+
+    pager.upper_mark = 1;
+    assert!(buffer::restore(&mut pager));
+    assert!(!buffer::restore(&mut pager));
+    assert_eq!(pager.upper_mark, 5);
+    assert_eq!(pager.num_logical_lines(), 31);
+    assert_eq!(pager.prompt, vec!["New prompt"]);
+    assert_eq!(pager.marks.get(&'a'), Some(&3));
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_temporary_buffer_filtered() {
+    use crate::buffer;
+
+    let mut pager = Pager::new().unwrap();
+    pager.cols = 10;
+    pager.set_text("a\nmatch one two\nb\nmatch");
+    pager.set_filter("match", false).unwrap();
+    // The position is kept within the rows of the line shown, not of the line
+    // at the same index in the whole output
+    pager.upper_mark = 1;
+    assert!(buffer::show(&mut pager, "help", "help"));
+    assert!(buffer::restore(&mut pager));
+    assert_eq!(pager.upper_mark, 1);
+}
+
+#[test]
+fn test_help_text() {
+    use crate::{buffer, input::Keymap};
+
+    let mut pager = Pager::new().unwrap();
+    let text = buffer::help_text(&pager);
+    assert!(text.contains("  <Down>, j "));
+    assert!(text.contains("Show this help screen"));
+
+    pager.set_keymap(Keymap::from_config("x = custom:open").unwrap());
+    assert!(buffer::help_text(&pager).contains("  x "));
+    assert!(buffer::help_text(&pager).contains("Run open"));
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_highlights() {
+    use crate::{buffer, highlight::parse_style};
+
+    let mut pager = Pager::new().unwrap();
+    let red = parse_style("red").unwrap();
+    pager.add_highlight("errors", "ERROR", red).unwrap();
+    pager.add_highlight("ids", r"id=\d+", red).unwrap();
+    assert!(pager.add_highlight("bad", "(", red).is_err());
+    // Adding a highlight with an existing name replaces it, keeping it's place
+    pager
+        .add_highlight("errors", "ERROR|FATAL", parse_style("bold").unwrap())
+        .unwrap();
+    let names: Vec<&str> = pager.highlights.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["errors", "ids"]);
+    assert_eq!(pager.highlights[0].pattern.as_str(), "ERROR|FATAL");
+
+    let text = buffer::help_text(&pager);
+    assert!(text.contains("Highlights"));
+    assert!(text.contains("  errors  "));
+    // Highlights are not applied to the help screen
+    assert!(buffer::show(&mut pager, &text, "help"));
+    assert!(pager.shown_highlights().is_empty());
+    assert!(buffer::restore(&mut pager));
+    assert_eq!(pager.shown_highlights().len(), 2);
+
+    assert!(pager.remove_highlight("ids"));
+    assert!(!pager.remove_highlight("ids"));
+    pager.clear_highlights();
+    assert!(pager.highlights.is_empty());
+    assert!(!buffer::help_text(&pager).contains("Highlights"));
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_filter() {
+    let mut pager = Pager::new().unwrap();
+    for i in 1..=30 {
+        writeln!(pager, "line {i}").unwrap();
+    }
+    pager.upper_mark = pager.row_of_line(12);
+
+    pager.set_filter("0$", false).unwrap();
+    assert_eq!(
+        pager.shown_texts().collect::<Vec<&str>>(),
+        ["line 10", "line 20", "line 30"]
+    );
+    // The first shown line after the one at the top comes to the top
+    assert_eq!(pager.upper_mark, 1);
+    assert_eq!(pager.original_line(2), 20);
+    assert_eq!(pager.shown_line(12), 2);
+    assert_eq!(pager.shown_line(20), 2);
+    assert_eq!(pager.shown_line(100), 3);
+
+    // Lines added later are filtered as they come
+    writeln!(pager, "line 31").unwrap();
+    write!(pager, "line 4").unwrap();
+    assert_eq!(pager.num_logical_lines(), 3);
+    writeln!(pager, "0").unwrap();
+    assert_eq!(pager.num_logical_lines(), 4);
+    assert_eq!(pager.original_line(4), 32);
+
+    // Clearing the filter keeps the line at the top
+    pager.clear_filter();
+    assert_eq!(pager.num_logical_lines(), 32);
+    assert_eq!(pager.line_of_row(pager.upper_mark), 20);
+
+    pager.set_filter("[02468]$", true).unwrap();
+    assert_eq!(pager.num_logical_lines(), 16);
+    assert_eq!(pager.original_line(1), 1);
+    assert_eq!(pager.original_line(2), 3);
+
+    assert!(pager.set_filter("(", false).is_err());
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_filter_context() {
+    let mut pager = Pager::new().unwrap();
+    pager.set_text("a\nb\nmatch\nc\nd\ne\nf\nmatch\ng\nmatch\nh\ni");
+    pager.set_filter_context(1);
+    pager.set_filter("match", false).unwrap();
+    let shown: Vec<usize> = (1..=pager.num_logical_lines())
+        .map(|l| pager.original_line(l))
+        .collect();
+    assert_eq!(shown, [2, 3, 4, 7, 8, 9, 10, 11]);
+
+    // Replacing the text filters it again
+    pager.set_text("match\nx\ny");
+    assert_eq!(pager.shown_texts().collect::<Vec<&str>>(), ["match", "x"]);
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_search_while_streaming() {
+    use crate::search::{search_from, MatchMove};
+    use regex::Regex;
+
+    let mut pager = Pager::new().unwrap();
+    pager.set_match_context(0);
+    for i in 0..20 {
+        writeln!(pager, "line {i}").unwrap();
+    }
+    let moved = search_from(&mut pager, Regex::new("7$").unwrap(), 0);
+    assert_eq!(moved, Some(MatchMove::Found));
+    assert_eq!(pager.search_idx.len(), 2);
+
+    // Matches in lines added later are found, but the pager stays where it is
+    // by default
+    write!(pager, "line 27").unwrap();
+    assert_eq!(pager.search_idx.len(), 2);
+    writeln!(pager).unwrap();
+    writeln!(pager, "line 28").unwrap();
+    assert_eq!(pager.search_idx.len(), 3);
+    assert_eq!(pager.search_idx[2].row, 20);
+    assert_eq!(pager.upper_mark, 7);
+
+    pager.set_jump_to_new_matches(true);
+    writeln!(pager, "line 29\nline 37").unwrap();
+    assert_eq!((pager.upper_mark, pager.search_mark), (23, 3));
+    assert_eq!(pager.message.0.as_ref().unwrap()[0], "Match 4 of 4");
+
+    // Rewrapping moves the matches to their new rows
+    pager.set_text(format!("{} 7\nline 7", "x".repeat(40)));
+    assert_eq!(pager.search_idx.len(), 2);
+    pager.cols = 20;
+    pager.readjust_wraps();
+    assert_eq!(pager.search_idx[0].row, 2);
+    assert_eq!(pager.search_idx[1].row, 3);
+    // The rows of the lines searched are carried over to the lines added
+    writeln!(pager, "{} 7", "y".repeat(30)).unwrap();
+    assert_eq!(pager.search_idx[2].row, 5);
+    assert_eq!(pager.search_scanned_rows, pager.num_lines());
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_search_api() {
+    use crate::{SearchMode, SearchOptions};
+
+    let mut pager = Pager::new().unwrap();
+    pager.set_match_context(0);
+    for i in 1..=30 {
+        writeln!(pager, "line {i}").unwrap();
+    }
+    assert_eq!(pager.next_match(), None);
+
+    pager.search("5$", SearchMode::Forward).unwrap();
+    let lines =
+        |pager: &Pager| -> Vec<usize> { pager.search_matches().iter().map(|m| m.line).collect() };
+    assert_eq!(lines(&pager), [5, 15, 25]);
+    assert_eq!(pager.current_match().unwrap().range, 5..6);
+    assert_eq!(pager.upper_mark, 4);
+    assert_eq!(pager.message.0.as_ref().unwrap()[0], "Match 1 of 3");
+    assert_eq!(pager.search_history(SearchMode::Forward), ["5$"]);
+    assert_eq!(pager.next_match().unwrap().line, 15);
+    assert_eq!(pager.prev_match().unwrap().line, 5);
+
+    // After a reverse search, the next match is the one before
+    pager.upper_mark = 20;
+    pager.search("0$", SearchMode::Reverse).unwrap();
+    assert_eq!(pager.current_match().unwrap().line, 30);
+    assert_eq!(pager.next_match().unwrap().line, 20);
+
+    // An invalid query leaves the search alone
+    assert!(pager
+        .search_with_options("(", SearchMode::Forward, SearchOptions::default())
+        .is_err());
+    assert_eq!(pager.current_match().unwrap().line, 20);
+
+    // Line numbers are those of the whole output when lines are hidden
+    pager.set_filter("^line 2", false).unwrap();
+    assert_eq!(lines(&pager), [20]);
+
+    pager.clear_search();
+    assert!(pager.search_matches().is_empty());
+    assert_eq!(pager.next_match(), None);
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_occur() {
+    use crate::{input::PagerState, search, SearchMode};
+
+    let mut pager = Pager::new().unwrap();
+    pager.rows = 5;
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {i}")).collect();
+    pager.set_text(lines.join("\n"));
+    pager.upper_mark = 10;
+    pager.search("0$", SearchMode::Forward).unwrap();
+
+    // The matching lines are listed with their line numbers, the first one after
+    // the top of the screen is focused
+    search::show_occur(&mut pager);
+    assert_eq!(
+        pager.wrap_lines,
+        vec![
+            vec!["10: line 10"],
+            vec!["20: line 20"],
+            vec!["30: line 30"]
+        ]
+    );
+    assert_eq!(pager.focused_line, Some(2));
+    assert!(PagerState::from_pager(&pager).occur);
+
+    // Going to a line of the list returns to it in the output
+    pager.focused_line = Some(3);
+    assert!(search::go_to_occurrence(&mut pager));
+    assert!(pager.saved_content.is_none());
+    assert_eq!(pager.upper_mark, 29);
+    assert_eq!(pager.search_mark, 2);
+    assert!(!search::go_to_occurrence(&mut pager));
+
+    pager.clear_search();
+    search::show_occur(&mut pager);
+    assert!(pager.saved_content.is_none());
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_match_context() {
+    use crate::SearchMode;
+
+    let mut pager = Pager::new().unwrap();
+    pager.rows = 10;
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {i}")).collect();
+    pager.set_text(lines.join("\n"));
+
+    // Rows are kept above the match, fewer of them on a small screen
+    pager.search("line 20", SearchMode::Forward).unwrap();
+    assert_eq!(pager.upper_mark, 16);
+    pager.rows = 3;
+    pager.next_match();
+    assert_eq!(pager.upper_mark, 18);
+    pager.set_match_context(0);
+    pager.next_match();
+    assert_eq!(pager.upper_mark, 19);
+
+    // Only the current match is highlighted in it's own style
+    pager.upper_mark = 0;
+    pager.search("line 2", SearchMode::Forward).unwrap();
+    assert_eq!(pager.current_match().unwrap().line, 2);
+    assert_eq!(pager.search_highlight(1).unwrap().current, Some(0..6));
+    assert_eq!(pager.search_highlight(19).unwrap().current, None);
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_search_scope() {
+    use crate::{buffer, search::SearchPrompt, utils::prompt::PromptHandler, SearchMode};
+
+    let mut pager = Pager::new().unwrap();
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {i}")).collect();
+    pager.set_text(lines.join("\n"));
+    pager.search("0$", SearchMode::Forward).unwrap();
+    assert_eq!(pager.search_matches().len(), 3);
+
+    // Only the matches in the lines searched are kept, and `n` stays in them
+    pager.set_search_scope(25, 15);
+    assert_eq!(pager.search_scope, Some((15, 25)));
+    assert_eq!(pager.search_matches().len(), 1);
+    assert_eq!(pager.next_match().unwrap().line, 20);
+    assert_eq!(pager.next_match().unwrap().line, 20);
+    // A new search is limited too
+    pager.search("line 1", SearchMode::Forward).unwrap();
+    assert_eq!(pager.search_matches().len(), 5);
+    let prompt = SearchPrompt::new(&mut pager, SearchMode::Forward);
+    assert_eq!(PromptHandler::<Vec<u8>>::prompt(&prompt), "Lines 15-25 /");
+    prompt.cancel();
+    // The scope does not apply to a temporary buffer, nor is it shown there
+    assert!(buffer::show(&mut pager, "line 10\nline 11", "help"));
+    let prompt = SearchPrompt::new(&mut pager, SearchMode::Forward);
+    assert_eq!(PromptHandler::<Vec<u8>>::prompt(&prompt), "/");
+    prompt.cancel();
+    pager.search("0$", SearchMode::Forward).unwrap();
+    assert_eq!(pager.search_matches().len(), 1);
+    assert!(buffer::restore(&mut pager));
+
+    pager.clear_search_scope();
+    assert_eq!(pager.search_matches().len(), 11);
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_fuzzy_search() {
+    use crate::{FuzzyOrder, SearchMode, SearchOptions};
+
+    let mut pager = Pager::new().unwrap();
+    pager.set_text("a_b_c\naxxbxxc\nnothing\nABC");
+    let options = SearchOptions {
+        fuzzy: true,
+        ..SearchOptions::default()
+    };
+    assert_eq!(options.flags(), "Fuzzy ");
+    pager
+        .search_with_options("abc", SearchMode::Forward, options)
+        .unwrap();
+    // Matches are visited in the order they appear by default
+    let lines =
+        |pager: &Pager| -> Vec<usize> { pager.search_matches().iter().map(|m| m.line).collect() };
+    assert_eq!(lines(&pager), [1, 2]);
+    assert_eq!(pager.current_match().unwrap().range, 0..5);
+
+    // Or from the best to the worst
+    pager.set_fuzzy_order(FuzzyOrder::Score);
+    let options = SearchOptions {
+        case: crate::SearchCase::Insensitive,
+        ..options
+    };
+    pager
+        .search_with_options("abc", SearchMode::Forward, options)
+        .unwrap();
+    assert_eq!(lines(&pager), [1, 2, 4]);
+    assert_eq!(pager.current_match().unwrap().line, 4);
+    assert_eq!(pager.next_match().unwrap().line, 1);
+    assert_eq!(pager.next_match().unwrap().line, 2);
+    assert_eq!(pager.message.0.as_ref().unwrap()[0], "Match 3 of 3");
+    assert_eq!(pager.next_match().unwrap().line, 4);
+    assert_eq!(pager.prev_match().unwrap().line, 2);
+}
@@ -0,0 +1,125 @@
+// ANSI escape sequences in the output
+//
+// Applications can send coloured text, which is shown as it is. Searching and
+// copying look at the text without it's escape sequences instead, since that is
+// the text the user sees.
+
+#[cfg(feature = "search")]
+use std::{borrow::Cow, ops::Range};
+
+// Length in bytes of the escape sequence at the start of `text`, which starts
+// with ESC. An unfinished sequence lasts until the end of the text
+fn escape_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    match bytes.get(1) {
+        // CSI sequences end with a character in the range @ to ~
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (b'@'..=b'~').contains(b))
+            .map_or(text.len(), |i| i + 3),
+        // OSC sequences end with BEL or ST
+        Some(b']') => {
+            let mut i = 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\x07' => return i + 1,
+                    b'\x1b' if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            text.len()
+        }
+        // Other escapes are two characters long
+        Some(_) => 1 + text[1..].chars().next().map_or(0, char::len_utf8),
+        None => 1,
+    }
+}
+
+// Split `text` into escape sequences and the text between them
+//
+// Yields whether each part is an escape sequence, along with the part.
+pub(crate) fn parts(text: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let (is_escape, len) = if rest.starts_with('\x1b') {
+            (true, escape_len(rest))
+        } else {
+            (false, rest.find('\x1b').unwrap_or(rest.len()))
+        };
+        let (part, after) = rest.split_at(len);
+        rest = after;
+        Some((is_escape, part))
+    })
+}
+
+// Whether `escape` is an SGR sequence that resets all the styles, like `ESC[0m`
+#[cfg(feature = "search")]
+pub(crate) fn is_reset(escape: &str) -> bool {
+    matches!(escape, "\x1b[m" | "\x1b[0m")
+}
+
+// Whether `escape` is an SGR sequence, which sets the style of the text after it
+#[cfg(feature = "search")]
+pub(crate) fn is_sgr(escape: &str) -> bool {
+    escape.starts_with("\x1b[") && escape.ends_with('m')
+}
+
+// Text with it's escape sequences removed, which remembers where each of it's
+// bytes comes from in the original text
+#[cfg(feature = "search")]
+pub(crate) struct Stripped<'a> {
+    text: Cow<'a, str>,
+    // Byte index in the original text of each byte of `text`. Empty if the
+    // original text has no escape sequences
+    offsets: Vec<usize>,
+}
+
+#[cfg(feature = "search")]
+impl<'a> Stripped<'a> {
+    pub(crate) fn new(original: &'a str) -> Self {
+        if !original.contains('\x1b') {
+            return Self {
+                text: Cow::Borrowed(original),
+                offsets: Vec::new(),
+            };
+        }
+        let mut text = String::with_capacity(original.len());
+        let mut offsets = Vec::with_capacity(original.len());
+        let mut start = 0;
+        for (is_escape, part) in parts(original) {
+            if !is_escape {
+                text.push_str(part);
+                offsets.extend(start..start + part.len());
+            }
+            start += part.len();
+        }
+        Self {
+            text: Cow::Owned(text),
+            offsets,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Byte range in the original text of the bytes in `range` of the stripped
+    // text. Escape sequences just before or after it are left out
+    pub(crate) fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        if self.offsets.is_empty() || range.is_empty() {
+            return range;
+        }
+        self.offsets[range.start]..self.offsets[range.end - 1] + 1
+    }
+}
+
+// Remove the ANSI escape sequences from `text`
+pub(crate) fn strip_ansi(text: &str) -> String {
+    parts(text)
+        .filter(|(is_escape, _)| !is_escape)
+        .map(|(_, part)| part)
+        .collect()
+}
@@ -0,0 +1,43 @@
+// Copying text to the system clipboard
+//
+// minus runs inside a terminal, which may well be on another machine than the
+// clipboard. Hence text is copied with the OSC 52 escape sequence, which asks
+// the terminal to put it in the clipboard. Not every terminal supports it, but
+// it needs no external dependencies and works over SSH.
+
+use std::io;
+
+// The default for the largest number of bytes copied at once, chosen so that the
+// escape sequence carries at most 100,000 bytes of base64. That is small enough
+// for tmux and most terminals
+pub(crate) const DEFAULT_LIMIT: usize = 74_994;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Encode `data` as standard base64 with padding
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Ask the terminal to copy `text` to the clipboard
+pub(crate) fn copy(out: &mut impl io::Write, text: &str) -> io::Result<()> {
+    write!(out, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    out.flush()
+}
@@ -1,22 +1,44 @@
 use std::io::Stdout;
 
-use super::term::cleanup;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+};
+
+use super::{clipboard, prompt, term::cleanup};
+use crate::{
+    buffer,
+    error::{AlternateScreenPagingError, CleanupError, SetupError},
+    input::{CopyTarget, CustomActionResult, InputEvent},
+    Pager,
+};
 #[cfg(feature = "search")]
-use crate::search;
-use crate::{error::AlternateScreenPagingError, input::InputEvent, Pager};
+use crate::{input::SearchScope, search};
 
 // This file contains the handle_input function to handle events
 
 // This function matches the given Option<InputEvent> and handles the event appropriately
+#[allow(clippy::too_many_lines)]
 pub(crate) fn handle_input(
-    ev: &Option<InputEvent>,
-    mut pager: &mut Pager,
-    mut out: &mut Stdout,
+    ev: Option<&InputEvent>,
+    pager: &mut Pager,
+    out: &mut Stdout,
     redraw: &mut bool,
-    #[cfg(feature = "search")] mut s_mark: &mut usize,
 ) -> Result<(), AlternateScreenPagingError> {
+    // Any event other than a digit consumes the pending count. No event at all
+    // leaves it alone, as that happens in the middle of a key sequence too
+    if matches!(ev, Some(e) if !matches!(e, InputEvent::Number(_))) && !pager.prefix_num.is_empty()
+    {
+        pager.prefix_num.clear();
+        *redraw = true;
+    }
     #[allow(clippy::match_same_arms)]
     match ev {
+        // Exiting from a temporary buffer returns to the output
+        Some(InputEvent::Exit) if pager.saved_content.is_some() => {
+            buffer::restore(pager);
+            *redraw = true;
+        }
         Some(InputEvent::Exit) => {
             pager.exit();
             return Ok(cleanup(out, &pager.exit_strategy, true)?);
@@ -42,59 +64,351 @@ pub(crate) fn handle_input(
             pager.line_numbers = *l;
             *redraw = true;
         }
-        #[cfg(feature = "search")]
-        Some(InputEvent::Search(m)) => {
-            pager.search_mode = *m;
-            // Get the query
-            let string = search::fetch_input(&mut out, pager.search_mode, pager.rows)?;
+        Some(InputEvent::Number(c)) => {
+            pager.prefix_num.push(*c);
+            // Ignore digits that would make the count overflow
+            if pager.prefix_num.parse::<usize>().is_err() {
+                pager.prefix_num.pop();
+            }
+            *redraw = true;
+        }
+        Some(InputEvent::GoToLine(n)) => {
+            pager.upper_mark = pager.row_of_line(pager.shown_line(*n));
+            *redraw = true;
+        }
+        Some(InputEvent::GoToPercent(p)) => {
+            pager.upper_mark = pager.row_of_line(pager.line_at_percent(*p));
+            *redraw = true;
+        }
+        Some(InputEvent::Custom(name)) => match pager.run_custom_action(name) {
+            Some(CustomActionResult::Exit) => {
+                pager.exit();
+                return Ok(cleanup(out, &pager.exit_strategy, true)?);
+            }
+            Some(CustomActionResult::Message(message)) => {
+                pager.send_message(message);
+                *redraw = true;
+            }
+            Some(CustomActionResult::Redraw) => *redraw = true,
+            Some(CustomActionResult::None) | None => {}
+        },
+        Some(InputEvent::UpdateMouseCapture(capture)) => {
+            if *capture {
+                execute!(out, EnableMouseCapture)
+                    .map_err(|e| SetupError::EnableMouseCapture(e.into()))?;
+            } else {
+                execute!(out, DisableMouseCapture)
+                    .map_err(|e| CleanupError::DisableMouseCapture(e.into()))?;
+            }
+            pager.mouse_capture = *capture;
+            pager.selection = None;
+            *redraw = true;
+        }
+        Some(InputEvent::MouseClick(row)) => {
+            let line = pager.line_of_row(*row);
+            pager.focused_line = Some(line);
+            pager.selection = Some((line, line));
+            *redraw = true;
+        }
+        Some(InputEvent::MouseDrag(row)) => {
+            let line = pager.line_of_row(*row);
+            if let Some((_, end)) = pager.selection.as_mut() {
+                *end = line;
+                *redraw = true;
+            }
+        }
+        Some(InputEvent::MouseRelease(row)) => {
+            let line = pager.line_of_row(*row);
+            if let Some((_, end)) = pager.selection.as_mut() {
+                *end = line;
+            }
+            // Only copy when lines were dragged over, a plain click just focuses
+            // the line
+            match pager.selection {
+                Some((start, end)) if start != end => {
+                    let text = pager.selected_text().unwrap_or_default();
+                    copy_lines(out, pager, &text, start.max(end) - start.min(end) + 1)?;
+                }
+                _ => pager.selection = None,
+            }
+            *redraw = true;
+        }
+        Some(InputEvent::FocusLine(line)) => {
+            let count = pager.num_logical_lines().max(1);
+            let line = (*line).clamp(1, count);
+            pager.focused_line = Some(line);
+            // Scroll just enough for the line to be on the screen
+            let (top, bottom) = pager.visible_lines();
+            if line < top {
+                pager.upper_mark = pager.row_of_line(line);
+            } else if line > bottom {
+                let end = if line < count {
+                    pager.row_of_line(line + 1)
+                } else {
+                    pager.num_lines()
+                };
+                pager.upper_mark = end.saturating_sub(pager.rows.saturating_sub(1));
+            }
+            *redraw = true;
+        }
+        Some(InputEvent::Help) => {
+            let text = buffer::help_text(pager);
+            if buffer::show(pager, &text, "Help, press q to return") {
+                *redraw = true;
+            }
+        }
+        Some(InputEvent::Copy(target)) => {
+            match target {
+                CopyTarget::Lines(n) => {
+                    let start = pager.cursor_line();
+                    let end = start
+                        .saturating_add(n.saturating_sub(1))
+                        .min(pager.num_logical_lines());
+                    let text = pager.text_of_lines(start, end);
+                    copy_lines(out, pager, &text, end.saturating_sub(start) + 1)?;
+                }
+                CopyTarget::Screen => {
+                    let (start, end) = pager.visible_lines();
+                    let text = pager.text_of_lines(start, end);
+                    copy_lines(out, pager, &text, end - start + 1)?;
+                }
+                CopyTarget::Selection => match pager.selection {
+                    Some((start, end)) => {
+                        let text = pager.selected_text().unwrap_or_default();
+                        copy_lines(out, pager, &text, start.max(end) - start.min(end) + 1)?;
+                    }
+                    None => pager.send_message("No lines selected. Press Enter"),
+                },
+                #[cfg(feature = "search")]
+                CopyTarget::Match => match search::match_text(pager) {
+                    Some(text) => {
+                        if copy(out, pager, &text)? {
+                            pager.send_message(
+                                "Copied the search match to the clipboard. Press Enter",
+                            );
+                        }
+                    }
+                    None => pager.send_message("No search match to copy. Press Enter"),
+                },
+            }
+            *redraw = true;
+        }
+        Some(InputEvent::SetMark(c)) => {
+            // Marks are kept as lines of the whole output, so that they stay
+            // valid when a filter changes the lines shown
+            let line = pager.original_line(pager.line_of_row(pager.upper_mark));
+            pager.marks.insert(*c, line);
+        }
+        Some(InputEvent::GoToMark(c)) => {
+            if let Some(line) = pager.marks.get(c) {
+                pager.upper_mark = pager.row_of_line(pager.shown_line(*line));
+            } else {
+                pager.send_message("Mark not set. Press Enter");
+            }
+            *redraw = true;
+        }
+        Some(InputEvent::GoToLinePrompt) => {
+            let string = prompt::fetch_input(out, &mut ":", pager.rows, pager.cols)?;
             if !string.is_empty() {
-                let regex = regex::Regex::new(&string);
-                if let Ok(r) = regex {
-                    pager.search_term = Some(r);
-                    // Prepare a index where search matches are found
-                    // and set it to pager.search_idx
-                    search::set_match_indices(&mut pager);
-                    // Move to
-                    search::next_match(&mut pager, &mut s_mark);
+                if let Ok(n) = string.trim().parse::<usize>() {
+                    pager.upper_mark = pager.row_of_line(pager.shown_line(n));
+                } else {
+                    pager.send_message("Invalid line number. Press Enter");
+                }
+            }
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::Filter(context)) if pager.saved_content.is_none() => {
+            let string = prompt::fetch_input(out, &mut "&", pager.rows, pager.cols)?;
+            // An empty pattern shows all the lines again, like in less
+            if string.is_empty() {
+                if pager.filter.is_some() {
+                    pager.clear_filter();
+                    pager.send_message("Showing all lines. Press Enter");
+                }
+            } else {
+                let (pattern, inverted) = match string.strip_prefix('!') {
+                    Some(pattern) => (pattern, true),
+                    None => (string.as_str(), false),
+                };
+                if let Ok(r) = pager.search_options.compile(pattern) {
+                    let context = context.unwrap_or(pager.filter_context);
+                    pager.replace_filter(Some(crate::filter::Filter::new(r, inverted, context)));
+                    let shown = pager.num_logical_lines();
+                    pager.send_message(format!(
+                        "Showing {shown} of {} lines. Press Enter",
+                        pager.wrap_lines.len()
+                    ));
                 } else {
-                    // Send invalid regex message at the prompt if invalid regex is given
                     pager.send_message("Invalid regular expression. Press Enter");
                 }
             }
             *redraw = true;
         }
         #[cfg(feature = "search")]
-        Some(InputEvent::NextMatch) if pager.search_term.is_some() => {
-            // If s_mark is less than the length of pager.search_idx
-            // and it is not page where the last match is present
-            // then increment the s_mark
-            if *s_mark < pager.search_idx.len().saturating_sub(1)
-                && pager.upper_mark + pager.rows < pager.num_lines()
-            {
-                *s_mark += 1;
+        Some(InputEvent::HighlightPrompt) => {
+            let string = prompt::fetch_input(out, &mut "+", pager.rows, pager.cols)?;
+            let string = string.trim();
+            if let Some(name) = string.strip_prefix('-') {
+                if pager.remove_highlight(name) {
+                    pager.send_message(format!("Removed highlight {name}. Press Enter"));
+                } else {
+                    pager.send_message(format!("No highlight named {name}. Press Enter"));
+                }
+            } else if !string.is_empty() {
+                // The pattern is the rest of the input, so that it can contain spaces
+                let mut parts = string.splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(name), Some(style), Some(pattern)) => {
+                        if let Some(style) = crate::highlight::parse_style(style) {
+                            if pager.add_highlight(name, pattern, style).is_ok() {
+                                pager.send_message(format!("Highlighting {name}. Press Enter"));
+                            } else {
+                                pager.send_message("Invalid regular expression. Press Enter");
+                            }
+                        } else {
+                            pager.send_message(format!("Unknown style {style}. Press Enter"));
+                        }
+                    }
+                    _ => pager.send_message("Expected: name style pattern. Press Enter"),
+                }
             }
-            // Go to the next match
-            search::next_match(&mut pager, &mut s_mark);
             *redraw = true;
         }
         #[cfg(feature = "search")]
-        Some(InputEvent::PrevMatch) if pager.search_term.is_some() => {
-            // If no matches, return immediately
-            if pager.search_idx.is_empty() {
-                return Ok(());
+        Some(InputEvent::Occur) => {
+            search::show_occur(pager);
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::GoToOccurrence) => {
+            if search::go_to_occurrence(pager) {
+                *redraw = true;
+            }
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::SetSearchScope(scope)) if pager.saved_content.is_none() => {
+            let lines = match scope {
+                SearchScope::All => None,
+                SearchScope::Screen => {
+                    let (top, bottom) = pager.visible_lines();
+                    Some((pager.original_line(top), pager.original_line(bottom)))
+                }
+                SearchScope::Mark(c) => {
+                    if let Some(line) = pager.marks.get(c) {
+                        Some((*line, pager.original_line(pager.cursor_line())))
+                    } else {
+                        pager.send_message("Mark not set. Press Enter");
+                        *redraw = true;
+                        return Ok(());
+                    }
+                }
+            };
+            if let Some((start, end)) = lines {
+                pager.set_search_scope(start, end);
+            } else {
+                pager.clear_search_scope();
+            }
+            match pager.search_scope {
+                Some((start, end)) => {
+                    pager.send_message(format!("Searching lines {start}-{end}. Press Enter"));
+                }
+                None => pager.send_message("Searching all lines. Press Enter"),
             }
-            // Decrement the s_mark and get the preceeding index
-            *s_mark = s_mark.saturating_sub(1);
-            let y = pager.search_idx[*s_mark];
-            // If the index is less than or equal to the upper_mark, then set y to the new upper_mark
-            if y < pager.upper_mark {
-                pager.upper_mark = y;
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::Search(m)) => {
+            pager.search_mode = *m;
+            let (rows, cols) = (pager.rows, pager.cols);
+            let mut search_prompt = search::SearchPrompt::new(pager, *m);
+            // Get the query
+            let string = prompt::fetch_input(out, &mut search_prompt, rows, cols)?;
+            if string.is_empty() {
+                search_prompt.cancel();
+            } else {
+                search_prompt.finish(string);
             }
             *redraw = true;
         }
         #[cfg(feature = "search")]
+        Some(InputEvent::UpdateSearchOptions(options)) => {
+            pager.search_options = *options;
+            // Search again for the current query with the new options
+            if pager.search_term.is_some() {
+                if let Some(r) = pager
+                    .search_query
+                    .as_ref()
+                    .and_then(|q| search::SearchTerm::new(q, *options).ok())
+                {
+                    search::search_from(pager, r, pager.upper_mark);
+                }
+            }
+            let flags = options.flags();
+            pager.send_message(format!(
+                "Search options: {}. Press Enter",
+                if flags.is_empty() {
+                    "default"
+                } else {
+                    flags.trim_end()
+                }
+            ));
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::NextMatch) if pager.search_term.is_some() => {
+            let moved = search::go_to_next_match(pager);
+            search::report_match(pager, moved);
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
+        Some(InputEvent::PrevMatch) if pager.search_term.is_some() => {
+            let moved = search::go_to_prev_match(pager);
+            search::report_match(pager, moved);
+            *redraw = true;
+        }
+        #[cfg(feature = "search")]
         Some(_) => return Ok(()),
         None => return Ok(()),
     }
     Ok(())
 }
+
+// Copy `text` to the clipboard if it is within the size limit, otherwise tell the
+// user that it is too large
+//
+// Returns whether the text was copied
+fn copy(
+    out: &mut Stdout,
+    pager: &mut Pager,
+    text: &str,
+) -> Result<bool, AlternateScreenPagingError> {
+    if text.len() > pager.clipboard_limit {
+        pager.send_message(format!(
+            "Cannot copy {} bytes, the limit is {}. Press Enter",
+            text.len(),
+            pager.clipboard_limit
+        ));
+        return Ok(false);
+    }
+    clipboard::copy(out, text)?;
+    Ok(true)
+}
+
+// Copy `text`, which consists of `count` lines, and confirm it to the user
+fn copy_lines(
+    out: &mut Stdout,
+    pager: &mut Pager,
+    text: &str,
+    count: usize,
+) -> Result<(), AlternateScreenPagingError> {
+    if copy(out, pager, text)? {
+        pager.send_message(if count == 1 {
+            "Copied 1 line to the clipboard. Press Enter".to_string()
+        } else {
+            format!("Copied {count} lines to the clipboard. Press Enter")
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// The history of search queries
+//
+// Forward and reverse searches keep separate histories, which can be saved to a
+// file set by the application. The file uses a format close to less's
+// `.lesshst`: a header line, then a line naming each section followed by the
+// entries of that section, each prefixed by a `"`.
+//
+// ```text
+// .minus-history-file:
+// .search
+// "first query
+// .search-reverse
+// "another query
+// ```
+
+use crate::SearchMode;
+use std::{fs, io, path::PathBuf};
+
+// The number of entries kept for each direction by default
+pub(crate) const DEFAULT_LIMIT: usize = 100;
+
+const HEADER: &str = ".minus-history-file:";
+const FORWARD_SECTION: &str = ".search";
+const REVERSE_SECTION: &str = ".search-reverse";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SearchHistory {
+    // Entries for each direction, oldest first
+    forward: Vec<String>,
+    reverse: Vec<String>,
+    limit: usize,
+    // File where the history is saved
+    path: Option<PathBuf>,
+}
+
+impl Default for SearchHistory {
+    fn default() -> Self {
+        Self {
+            forward: Vec::new(),
+            reverse: Vec::new(),
+            limit: DEFAULT_LIMIT,
+            path: None,
+        }
+    }
+}
+
+impl SearchHistory {
+    // Get the entries for the search direction `mode`, oldest first
+    pub(crate) fn entries(&self, mode: SearchMode) -> &[String] {
+        if mode == SearchMode::Reverse {
+            &self.reverse
+        } else {
+            &self.forward
+        }
+    }
+
+    fn entries_mut(&mut self, mode: SearchMode) -> &mut Vec<String> {
+        if mode == SearchMode::Reverse {
+            &mut self.reverse
+        } else {
+            &mut self.forward
+        }
+    }
+
+    // Add `query` as the most recent entry, removing an earlier copy of it and
+    // the oldest entries over the limit
+    pub(crate) fn add(&mut self, mode: SearchMode, query: String) {
+        if query.is_empty() || query.contains('\n') {
+            return;
+        }
+        let limit = self.limit;
+        let entries = self.entries_mut(mode);
+        entries.retain(|e| *e != query);
+        entries.push(query);
+        let excess = entries.len().saturating_sub(limit);
+        entries.drain(..excess);
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        for entries in [&mut self.forward, &mut self.reverse] {
+            let excess = entries.len().saturating_sub(limit);
+            entries.drain(..excess);
+        }
+    }
+
+    // Save the history to `path` from now on, and add the entries already saved
+    // in it. A missing file is not an error, it is created on the next save
+    pub(crate) fn set_path(&mut self, path: PathBuf) -> io::Result<()> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        self.path = Some(path);
+        self.parse(&text);
+        Ok(())
+    }
+
+    // Add the entries in the saved history `text`
+    pub(crate) fn parse(&mut self, text: &str) {
+        let mut mode = None;
+        for line in text.lines() {
+            match line {
+                FORWARD_SECTION => mode = Some(SearchMode::Forward),
+                REVERSE_SECTION => mode = Some(SearchMode::Reverse),
+                // Other sections, including the header, are skipped
+                _ if line.starts_with('.') => mode = None,
+                _ => {
+                    if let (Some(mode), Some(query)) = (mode, line.strip_prefix('"')) {
+                        self.add(mode, query.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // Get the history in the format of the history file
+    pub(crate) fn serialize(&self) -> String {
+        let mut text = String::from(HEADER);
+        text.push('\n');
+        for (section, entries) in [
+            (FORWARD_SECTION, &self.forward),
+            (REVERSE_SECTION, &self.reverse),
+        ] {
+            text.push_str(section);
+            text.push('\n');
+            for entry in entries {
+                text.push('"');
+                text.push_str(entry);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    // Write the history to it's file, if one is set
+    pub(crate) fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, self.serialize()),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// A single line text editor, used for the input at the prompt
+//
+// The editor only keeps the text and the cursor and knows how to edit them with
+// the usual readline style keys. Reading the keys and drawing is left to the
+// caller, see `prompt::fetch_input`.
+//
+// Characters of zero width, like combining accents, are treated as part of the
+// character before them, so that they are moved over and deleted together with
+// it.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use textwrap::core::display_width;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct LineEditor {
+    text: String,
+    // Byte index of the cursor in `text`, always at a character boundary
+    cursor: usize,
+    // Byte index of the first character shown when the text is too long to
+    // fit on the screen
+    offset: usize,
+    // The history entry shown, if the user is browsing the history
+    history_idx: Option<usize>,
+    // The text typed before browsing the history, shown again after the most
+    // recent entry
+    draft: String,
+}
+
+fn char_width(c: char) -> usize {
+    display_width(c.encode_utf8(&mut [0; 4]))
+}
+
+impl LineEditor {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Replace the text and put the cursor at it's end
+    pub(crate) fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.offset = 0;
+    }
+
+    // Show the entry of `history` before the one shown, or the most recent one
+    // if none is shown yet. `history` is ordered from the oldest entry
+    //
+    // Returns false if there is no older entry
+    pub(crate) fn history_prev(&mut self, history: &[String]) -> bool {
+        let idx = match self.history_idx {
+            Some(0) => return false,
+            Some(idx) => idx.min(history.len()).saturating_sub(1),
+            None if history.is_empty() => return false,
+            None => {
+                self.draft = std::mem::take(&mut self.text);
+                history.len() - 1
+            }
+        };
+        self.history_idx = Some(idx);
+        self.set_text(history[idx].clone());
+        true
+    }
+
+    // Show the entry of `history` after the one shown, or the text typed before
+    // browsing the history after the most recent entry
+    //
+    // Returns false if the history is not being browsed
+    pub(crate) fn history_next(&mut self, history: &[String]) -> bool {
+        let Some(idx) = self.history_idx else {
+            return false;
+        };
+        if let Some(entry) = history.get(idx + 1) {
+            self.history_idx = Some(idx + 1);
+            self.set_text(entry.clone());
+        } else {
+            self.history_idx = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.set_text(draft);
+        }
+        true
+    }
+
+    // Byte index of the start of the character before the cursor, including the
+    // zero width characters attached to it
+    fn prev_boundary(&self, from: usize) -> usize {
+        let mut idx = from;
+        for (i, c) in self.text[..from].char_indices().rev() {
+            idx = i;
+            if char_width(c) != 0 {
+                break;
+            }
+        }
+        idx
+    }
+
+    // Byte index of the end of the character after the cursor, including the
+    // zero width characters attached to it
+    fn next_boundary(&self, from: usize) -> usize {
+        let mut chars = self.text[from..].char_indices();
+        match chars.next() {
+            Some((_, c)) => {
+                let mut end = from + c.len_utf8();
+                for (i, c) in chars {
+                    if char_width(c) != 0 {
+                        break;
+                    }
+                    end = from + i + c.len_utf8();
+                }
+                end
+            }
+            None => from,
+        }
+    }
+
+    // Byte index of the start of the word before `from`
+    fn prev_word(&self, from: usize) -> usize {
+        let before = &self.text[..from];
+        let end = before.trim_end().len();
+        before[..end]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    // Byte index of the end of the word after `from`
+    fn next_word(&self, from: usize) -> usize {
+        let after = &self.text[from..];
+        let start = after.len() - after.trim_start().len();
+        after[start..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(self.text.len(), |(i, _)| from + start + i)
+    }
+
+    pub(crate) fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // Apply an editing key. Returns whether the key was an editing key
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let KeyEvent { code, modifiers } = key;
+        let ctrl = modifiers == KeyModifiers::CONTROL;
+        let alt = modifiers == KeyModifiers::ALT;
+        match code {
+            KeyCode::Left if ctrl => self.cursor = self.prev_word(self.cursor),
+            KeyCode::Char('b') if alt => self.cursor = self.prev_word(self.cursor),
+            KeyCode::Right if ctrl => self.cursor = self.next_word(self.cursor),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(self.cursor),
+            KeyCode::Left => self.cursor = self.prev_boundary(self.cursor),
+            KeyCode::Char('b') if ctrl => self.cursor = self.prev_boundary(self.cursor),
+            KeyCode::Right => self.cursor = self.next_boundary(self.cursor),
+            KeyCode::Char('f') if ctrl => self.cursor = self.next_boundary(self.cursor),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Backspace if alt => self.delete_to(self.prev_word(self.cursor)),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.prev_word(self.cursor)),
+            KeyCode::Backspace => self.delete_to(self.prev_boundary(self.cursor)),
+            KeyCode::Char('h') if ctrl => self.delete_to(self.prev_boundary(self.cursor)),
+            KeyCode::Delete => self.delete_to(self.next_boundary(self.cursor)),
+            KeyCode::Char('d') if ctrl => self.delete_to(self.next_boundary(self.cursor)),
+            KeyCode::Char('d') if alt => self.delete_to(self.next_word(self.cursor)),
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char('k') if ctrl => self.delete_to(self.text.len()),
+            KeyCode::Char(c)
+                if modifiers == KeyModifiers::NONE || modifiers == KeyModifiers::SHIFT =>
+            {
+                self.insert(c);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // Delete the text between the cursor and `to`, leaving the cursor at the
+    // start of the deleted text
+    fn delete_to(&mut self, to: usize) {
+        let (start, end) = (self.cursor.min(to), self.cursor.max(to));
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    // Get the part of the text that fits in `width` columns, scrolled so that
+    // the cursor is visible, and the column of the cursor in it
+    pub(crate) fn visible(&mut self, width: usize) -> (&str, usize) {
+        // Keep one column for the cursor at the end of the text
+        let width = width.saturating_sub(1).max(1);
+        if self.offset > self.cursor || self.offset > self.text.len() {
+            self.offset = self.prev_boundary(self.cursor.min(self.text.len()));
+        }
+        while display_width(&self.text[self.offset..self.cursor]) > width {
+            self.offset = self.next_boundary(self.offset);
+        }
+        let mut end = self.offset;
+        let mut used = 0;
+        while end < self.text.len() {
+            let next = self.next_boundary(end);
+            let w = display_width(&self.text[end..next]);
+            if used + w > width {
+                break;
+            }
+            used += w;
+            end = next;
+        }
+        let column = display_width(&self.text[self.offset..self.cursor]);
+        (&self.text[self.offset..end], column)
+    }
+}
@@ -2,7 +2,13 @@
 //
 // The `term` module provide functions for setup/teardown of
 // the terminal
+pub(crate) mod ansi;
+pub(crate) mod clipboard;
 pub(crate) mod ev_handler;
+#[cfg(feature = "search")]
+pub(crate) mod history;
+pub(crate) mod line_editor;
+pub(crate) mod prompt;
 pub(crate) mod term;
 
 use crossterm::{
@@ -16,7 +22,7 @@ use std::{convert::TryFrom, io};
 use crate::{AlternateScreenPagingError, Pager};
 
 #[cfg(feature = "search")]
-use crate::search::highlight_line_matches;
+use crate::highlight::{highlight_rows, HighlightRule, SearchHighlight};
 
 // Writes the given `lines` to the given `out`put.
 //
@@ -64,6 +70,18 @@ pub(crate) fn draw(
             prompt = prompt.first().unwrap(),
             reset = Attribute::Reset,
         )?;
+        // Show the pending count at the right end of the prompt line
+        if !pager.prefix_num.is_empty() {
+            write!(
+                out,
+                "{}{}",
+                MoveTo(
+                    u16::try_from(pager.cols.saturating_sub(pager.prefix_num.len())).unwrap(),
+                    u16::try_from(pager.rows).unwrap()
+                ),
+                pager.prefix_num
+            )?;
+        }
     }
 
     out.flush().map_err(AlternateScreenPagingError::Draw)
@@ -72,7 +90,7 @@ pub(crate) fn draw(
 // Write the lines to the terminal
 pub(crate) fn write_lines(
     out: &mut impl io::Write,
-    mut pager: &mut Pager,
+    pager: &mut Pager,
 ) -> Result<(), AlternateScreenPagingError> {
     let line_count = pager.num_lines();
     // Reduce one row for prompt
@@ -101,21 +119,32 @@ pub(crate) fn write_lines(
 
     let displayed_lines = match pager.line_numbers {
         LineNumbers::AlwaysOff | LineNumbers::Disabled => {
-            // Get the unnested (flattened) lines and display them
-            #[cfg_attr(not(feature = "search"), allow(unused_mut))]
-            let mut lines = pager
-                .get_flattened_lines()
-                .skip(pager.upper_mark)
-                .take(rows.min(line_count))
-                .collect::<Vec<String>>();
-            // If search is enabled and there is a query, then highlight the matches
-            #[cfg(feature = "search")]
-            if let Some(st) = &pager.search_term {
-                for mut line in &mut lines {
-                    highlight_line_matches(&mut line, st);
+            // Only the logical lines that are on the screen are copied
+            let (top, count) = (pager.upper_mark, rows.min(line_count));
+            let mut displayed: Vec<String> = Vec::with_capacity(count);
+            let mut first_row = 0;
+            #[cfg_attr(not(feature = "search"), allow(unused_variables))]
+            for (idx, line) in pager.shown_lines().enumerate() {
+                if first_row >= top + count {
+                    break;
+                }
+                if first_row + line.len() > top {
+                    #[cfg_attr(not(feature = "search"), allow(unused_mut))]
+                    let mut line = line.clone();
+                    // If search is enabled, highlight the matches of the query and
+                    // of the highlight rules
+                    #[cfg(feature = "search")]
+                    if pager.search_term.is_some() || !pager.shown_highlights().is_empty() {
+                        let search = pager.search_highlight(idx);
+                        let text = pager.shown_text(idx).unwrap_or_default();
+                        highlight_rows(&mut line, text, search.as_ref(), pager.shown_highlights());
+                    }
+                    displayed.extend(line.into_iter().skip(top.saturating_sub(first_row)));
                 }
+                first_row += line.len();
             }
-            lines
+            displayed.truncate(count);
+            displayed
         }
         LineNumbers::AlwaysOn | LineNumbers::Enabled => {
             // Compute the length of a number as a string without allocating.
@@ -129,23 +158,34 @@ pub(crate) fn write_lines(
                 clippy::cast_sign_loss,
                 clippy::cast_precision_loss
             )]
-            let len_line_number = (line_count as f64).log10().floor() as usize + 1;
+            let len_line_number = (line_count.max(pager.wrap_lines.len()) as f64)
+                .log10()
+                .floor() as usize
+                + 1;
             // Get the line number annotated lines, optionally with search highlights, if
-            // there is a search query
+            // there is a search query or highlight rules
             annotate_line_numbers(
-                pager.get_lines(),
+                pager.shown_texts(),
+                |idx| pager.original_line(idx + 1),
                 len_line_number,
                 pager.cols,
                 #[cfg(feature = "search")]
-                &pager.search_term,
+                |idx| pager.search_highlight(idx),
+                #[cfg(feature = "search")]
+                pager.shown_highlights(),
             )
-            .iter()
+            .into_iter()
             .skip(pager.upper_mark)
             .take(rows.min(line_count))
-            .map(ToOwned::to_owned)
             .collect()
         }
     };
+    // Highlight the focused line and the lines selected with the mouse
+    let displayed_lines = if pager.focused_line.is_some() || pager.selection.is_some() {
+        decorate_lines(pager, displayed_lines)
+    } else {
+        displayed_lines
+    };
     // Join the lines and display them at once
     // This is because, writing to console is slow
     //
@@ -155,6 +195,36 @@ pub(crate) fn write_lines(
     Ok(())
 }
 
+// Show the rows of the selected lines in reverse video and the rows of the focused
+// line in bold
+fn decorate_lines(pager: &Pager, lines: Vec<String>) -> Vec<String> {
+    let selection = pager
+        .selection
+        .map(|(start, end)| start.min(end)..=start.max(end));
+    // The logical line of the current row and the number of it's rows left to display
+    let mut line = pager.line_of_row(pager.upper_mark);
+    let mut rows_left = pager.shown_rows(line - 1).map_or(0, |l| {
+        (pager.row_of_line(line) + l.len()).saturating_sub(pager.upper_mark)
+    });
+    lines
+        .into_iter()
+        .map(|row| {
+            if rows_left == 0 {
+                line += 1;
+                rows_left = pager.shown_rows(line - 1).map_or(0, Vec::len);
+            }
+            rows_left = rows_left.saturating_sub(1);
+            if selection.as_ref().is_some_and(|s| s.contains(&line)) {
+                format!("{}{row}{}", Attribute::Reverse, Attribute::Reset)
+            } else if pager.focused_line == Some(line) {
+                format!("{}{row}{}", Attribute::Bold, Attribute::Reset)
+            } else {
+                row
+            }
+        })
+        .collect()
+}
+
 /// Enum indicating whether to display the line numbers or not.
 ///
 /// Note that displaying line numbers may be less performant than not doing it.
@@ -202,26 +272,36 @@ impl std::ops::Not for LineNumbers {
 }
 
 // Add line numbers to all the lines taking into considerations the wraps
-fn annotate_line_numbers(
-    mut lines: Vec<Vec<String>>,
+//
+// `texts` is the text of the lines as it was given, which is wrapped to the
+// columns left by the line numbers. `line_number` gives the number shown for the
+// line at an index of `texts`
+#[cfg_attr(not(feature = "search"), allow(clippy::extra_unused_lifetimes))]
+fn annotate_line_numbers<'s, 't>(
+    texts: impl Iterator<Item = &'t str>,
+    line_number: impl Fn(usize) -> usize,
     len_line_number: usize,
     cols: usize,
-    #[cfg(feature = "search")] search_term: &Option<regex::Regex>,
+    #[cfg(feature = "search")] search: impl Fn(usize) -> Option<SearchHighlight<'s>>,
+    #[cfg(feature = "search")] highlights: &[HighlightRule],
 ) -> Vec<String> {
     // Calculate the amount of space required for the numbering ie. length of line
     // numbers + . + 2 spaces and wrap according to it
     let padding = len_line_number + 3;
-    for (idx, line) in lines.iter_mut().enumerate() {
-        crate::rewrap(line, cols.saturating_sub(padding));
+    let mut lines = Vec::new();
+    for (idx, text) in texts.enumerate() {
+        let mut line = crate::wrap_str(text, cols.saturating_sub(padding));
+        #[cfg(feature = "search")]
+        {
+            // Highlight the lines
+            let search = search(idx);
+            if search.is_some() || !highlights.is_empty() {
+                highlight_rows(&mut line, text, search.as_ref(), highlights);
+            }
+        }
 
         // Insert the line numbers
-        #[cfg_attr(not(feature = "search"), allow(unused_mut))]
-        for mut row in line.iter_mut() {
-            #[cfg(feature = "search")]
-            if let Some(st) = search_term {
-                // Highlight the lines
-                highlight_line_matches(&mut row, st);
-            }
+        for row in &mut line {
             // Make the formatted text
             // If function is called in a test run, reove the bold and reset
             // sequences because at that time we care more about correctness than
@@ -230,24 +310,24 @@ fn annotate_line_numbers(
                 format!(
                     " {bold}{number: >len$}.{reset} ",
                     bold = crossterm::style::Attribute::Bold,
-                    number = idx + 1,
+                    number = line_number(idx),
                     len = len_line_number,
                     reset = crossterm::style::Attribute::Reset
                 )
             } else {
                 format!(
                     " {number: >len$}. ",
-                    number = idx + 1,
+                    number = line_number(idx),
                     len = len_line_number,
                 )
             };
             // Insert line numbers at the beginning
             row.insert_str(0, &fmt_numbers);
         }
+        lines.append(&mut line);
     }
 
-    // Return the flattened lines
-    lines.iter().flatten().map(ToOwned::to_owned).collect()
+    lines
 }
 
 #[cfg(test)]
@@ -0,0 +1,219 @@
+// Reading a line of input from the user at the prompt site
+//
+// This is used for any input that minus needs from the user, like search
+// queries or line numbers to jump to. The text is edited with a `LineEditor`,
+// so every prompt gets the same editing keys.
+//
+// Pasted text arrives as ordinary key presses, since crossterm 0.20 does not
+// support bracketed paste. It is told apart from typing by the keys coming in a
+// burst, faster than anyone types: a newline in such a burst is pasted and does
+// not accept the input. Newlines within pasted text are replaced with spaces,
+// and one at it's end is left out.
+
+use super::line_editor::LineEditor;
+use crate::error::AlternateScreenPagingError;
+use crossterm::{
+    cursor::{self, MoveTo},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal::{Clear, ClearType},
+};
+use std::time::{Duration, Instant};
+use textwrap::core::display_width;
+
+// Draw `prompt` followed by the visible part of the text in `editor` on the
+// prompt line and place the cursor
+fn draw_input(
+    out: &mut impl std::io::Write,
+    prompt: &str,
+    editor: &mut LineEditor,
+    rows: usize,
+    cols: usize,
+) -> Result<(), AlternateScreenPagingError> {
+    let prompt_width = display_width(prompt);
+    let (text, column) = editor.visible(cols.saturating_sub(prompt_width));
+    #[allow(clippy::cast_possible_truncation)]
+    write!(
+        out,
+        "{}{}{prompt}{text}{}",
+        MoveTo(0, rows as u16),
+        Clear(ClearType::CurrentLine),
+        MoveTo((prompt_width + column) as u16, rows as u16),
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+// Keys read less than this apart are part of a burst, as only pasted text comes
+// in that fast
+const BURST_GAP: Duration = Duration::from_millis(5);
+// Number of keys in a burst before a newline that make it part of pasted text,
+// so that a key and Enter typed quickly while the pager is busy still accept
+// the input
+const PASTE_MIN_KEYS: usize = 3;
+
+// Keeps track of bursts of keys, to tell pasted text from typed text
+#[derive(Default)]
+pub(crate) struct PasteDetector {
+    last_key: Option<Instant>,
+    // Number of keys in the current burst before the last key
+    burst: usize,
+}
+
+impl PasteDetector {
+    // Note that a key was read at `now`
+    pub(crate) fn key_read(&mut self, now: Instant) {
+        self.burst = match self.last_key {
+            Some(last) if now.saturating_duration_since(last) < BURST_GAP => self.burst + 1,
+            _ => 0,
+        };
+        self.last_key = Some(now);
+    }
+
+    // Whether the Enter key read last is a newline in pasted text, given whether
+    // more keys are already waiting to be read after it
+    pub(crate) fn is_pasted_newline(&self, more_waiting: bool) -> bool {
+        self.burst >= PASTE_MIN_KEYS || (more_waiting && self.burst > 0)
+    }
+}
+
+// What a prompt does beyond editing the text
+//
+// A plain `&str` is a prompt that only shows itself before the text.
+pub(crate) trait PromptHandler<O> {
+    // The text shown before the input
+    fn prompt(&self) -> String;
+
+    // Previous inputs that Up and Down go through, ordered from the oldest one
+    fn history(&self) -> &[String] {
+        &[]
+    }
+
+    // Handle a key that is not an editing key. Returns whether it was handled,
+    // in which case the prompt is redrawn and `on_change` is called again
+    fn handle_key(&mut self, _key: KeyEvent) -> bool {
+        false
+    }
+
+    // Called with the text whenever it changed, once no more keys are waiting to
+    // be read. Hence a burst of keys, like pasted text, calls it only once. It
+    // may redraw the screen, the prompt line is redrawn after it
+    fn on_change(&mut self, _out: &mut O, _text: &str) -> Result<(), AlternateScreenPagingError> {
+        Ok(())
+    }
+}
+
+impl<O> PromptHandler<O> for &str {
+    fn prompt(&self) -> String {
+        (*self).to_string()
+    }
+}
+
+// What a key did at the prompt
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Edit {
+    // Nothing, the key is not used at the prompt
+    Ignored,
+    // The text was edited or the cursor moved
+    Text,
+    // The prompt handled the key itself
+    Prompt,
+}
+
+// Apply `key` to the input at the prompt of `handler`
+//
+// The handler sees the key first, so that it's own keys are not taken as
+// editing keys.
+pub(crate) fn edit<O>(
+    editor: &mut LineEditor,
+    handler: &mut impl PromptHandler<O>,
+    key: KeyEvent,
+) -> Edit {
+    if handler.handle_key(key) {
+        Edit::Prompt
+    } else if editor.handle_key(key) {
+        Edit::Text
+    } else {
+        Edit::Ignored
+    }
+}
+
+// Fetch a line of input from the user at the prompt of `handler`
+//
+// Returns an empty string if the user cancels the input with `Esc`
+pub(crate) fn fetch_input<O: std::io::Write>(
+    out: &mut O,
+    handler: &mut impl PromptHandler<O>,
+    rows: usize,
+    cols: usize,
+) -> Result<String, AlternateScreenPagingError> {
+    let mut editor = LineEditor::new();
+    let mut paste = PasteDetector::default();
+    // The text `on_change` was last called with, none if it must be called again
+    let mut reported = Some(String::new());
+    write!(out, "{}", cursor::Show)?;
+    draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+    loop {
+        if event::poll(Duration::from_millis(10))
+            .map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?
+        {
+            let ev =
+                event::read().map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?;
+            if matches!(ev, Event::Key(_)) {
+                paste.key_read(Instant::now());
+            }
+            match ev {
+                // If Esc is pressed, cancel the input
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc,
+                    modifiers: KeyModifiers::NONE,
+                }) => {
+                    write!(out, "{}", cursor::Hide)?;
+                    return Ok(String::new());
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    modifiers: KeyModifiers::NONE,
+                }) => {
+                    let more_waiting = event::poll(Duration::ZERO)
+                        .map_err(|e| AlternateScreenPagingError::HandleEvent(e.into()))?;
+                    if paste.is_pasted_newline(more_waiting) {
+                        // Pasted lines are joined with spaces
+                        if more_waiting {
+                            editor.insert(' ');
+                            draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+                        }
+                        continue;
+                    }
+                    write!(out, "{}", cursor::Hide)?;
+                    // Return the string when enter is pressed
+                    return Ok(editor.text().to_string());
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Up,
+                    modifiers: KeyModifiers::NONE,
+                }) if editor.history_prev(handler.history()) => {
+                    draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Down,
+                    modifiers: KeyModifiers::NONE,
+                }) if editor.history_next(handler.history()) => {
+                    draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+                }
+                Event::Key(key) => match edit(&mut editor, handler, key) {
+                    Edit::Ignored => {}
+                    Edit::Text => draw_input(out, &handler.prompt(), &mut editor, rows, cols)?,
+                    Edit::Prompt => {
+                        reported = None;
+                        draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+                    }
+                },
+                _ => {}
+            }
+        } else if reported.as_deref() != Some(editor.text()) {
+            reported = Some(editor.text().to_string());
+            handler.on_change(out, editor.text())?;
+            draw_input(out, &handler.prompt(), &mut editor, rows, cols)?;
+        }
+    }
+}
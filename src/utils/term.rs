@@ -14,6 +14,8 @@ use std::io;
 // When `dynamic` is set to true, `minus` wll exit with an error if the stdout is nt
 // a TTY.
 //
+// `mouse_capture` tells whether mouse events should be captured.
+//
 // ## Errors
 //
 // Setting up the terminal can fail, see [`SetupError`](SetupError).
@@ -21,6 +23,7 @@ pub(crate) fn setup(
     stdout: &io::Stdout,
     dynamic: bool,
     setup_screen: bool,
+    mouse_capture: bool,
 ) -> std::result::Result<(), SetupError> {
     let mut out = stdout.lock();
 
@@ -40,6 +43,10 @@ pub(crate) fn setup(
             .map_err(|e| SetupError::AlternateScreen(e.into()))?;
         terminal::enable_raw_mode().map_err(|e| SetupError::RawMode(e.into()))?;
         execute!(out, cursor::Hide).map_err(|e| SetupError::HideCursor(e.into()))?;
+        if mouse_capture {
+            execute!(out, event::EnableMouseCapture)
+                .map_err(|e| SetupError::EnableMouseCapture(e.into()))?;
+        }
     }
     Ok(())
 }
@@ -407,3 +407,313 @@ fn test_draw_no_overflow() {
         .expect("Should have written valid UTF-8")
         .contains(TEXT),);
 }
+
+#[test]
+fn draw_pending_count() {
+    let lines = "A line\nAnother line";
+
+    let mut out = Vec::with_capacity(lines.len());
+    let mut pager = Pager::new().unwrap();
+    pager.set_text(lines);
+    pager.prefix_num = "42".to_string();
+
+    draw(&mut out, &mut pager).expect("Should have written");
+
+    let res = String::from_utf8(out).expect("Should have written valid UTF-8");
+    assert!(res.ends_with(&format!("{}42", MoveTo(78, 10))));
+}
+
+#[test]
+fn draw_selection() {
+    let mut pager = Pager::new().unwrap();
+    pager.set_text("one\ntwo\nthree");
+    pager.selection = Some((1, 2));
+    pager.focused_line = Some(3);
+    let mut out = Vec::new();
+    write_lines(&mut out, &mut pager).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "\r{rev}one{reset}\n\r{rev}two{reset}\n\r{bold}three{reset}\n",
+            rev = Attribute::Reverse,
+            bold = Attribute::Bold,
+            reset = Attribute::Reset
+        )
+    );
+}
+
+#[test]
+fn test_base64_encode() {
+    use super::clipboard::base64_encode;
+
+    assert_eq!(base64_encode(b""), "");
+    assert_eq!(base64_encode(b"f"), "Zg==");
+    assert_eq!(base64_encode(b"fo"), "Zm8=");
+    assert_eq!(base64_encode(b"foo"), "Zm9v");
+    assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64_encode("\u{e9}t\u{e9}".as_bytes()), "w6l0w6k=");
+}
+
+#[test]
+fn test_strip_ansi() {
+    use super::ansi::strip_ansi;
+
+    assert_eq!(strip_ansi("plain"), "plain");
+    assert_eq!(strip_ansi("\x1b[1;31mred\x1b[0m text"), "red text");
+    assert_eq!(
+        strip_ansi("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07"),
+        "link"
+    );
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn test_stripped_ranges() {
+    use super::ansi::{parts, Stripped};
+
+    let text = "a\x1b[31mbc\x1b[0m\x1b]8;;x\x07d";
+    let stripped = Stripped::new(text);
+    assert_eq!(stripped.text(), "abcd");
+    assert_eq!(&text[stripped.original_range(1..3)], "bc");
+    assert_eq!(&text[stripped.original_range(0..4)], text);
+    assert_eq!(
+        parts(text).collect::<Vec<_>>(),
+        [
+            (false, "a"),
+            (true, "\x1b[31m"),
+            (false, "bc"),
+            (true, "\x1b[0m"),
+            (true, "\x1b]8;;x\x07"),
+            (false, "d"),
+        ]
+    );
+    // Text without escape sequences is kept as it is
+    assert_eq!(Stripped::new("plain").original_range(1..3), 1..3);
+}
+
+// A line editor with `text` typed into it
+fn typed(text: &str) -> super::line_editor::LineEditor {
+    let mut editor = super::line_editor::LineEditor::new();
+    text.chars().for_each(|c| editor.insert(c));
+    editor
+}
+
+#[test]
+fn test_line_editor_editing() {
+    use super::line_editor::LineEditor;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+    let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+
+    let mut editor = LineEditor::new();
+    for c in "foo bar baz".chars() {
+        assert!(editor.handle_key(key(KeyCode::Char(c))));
+    }
+    assert_eq!(editor.text(), "foo bar baz");
+
+    // Insert in the middle
+    editor.handle_key(alt('b'));
+    editor.handle_key(key(KeyCode::Left));
+    editor.handle_key(key(KeyCode::Char('!')));
+    assert_eq!(editor.text(), "foo bar! baz");
+
+    // Delete the word before the cursor
+    editor.handle_key(ctrl('w'));
+    assert_eq!(editor.text(), "foo  baz");
+
+    // Kill to the end and to the start
+    editor.handle_key(ctrl('k'));
+    assert_eq!(editor.text(), "foo ");
+    editor.handle_key(key(KeyCode::Left));
+    editor.handle_key(ctrl('u'));
+    assert_eq!(editor.text(), " ");
+
+    // Home, End and Delete
+    editor = typed("abc");
+    editor.handle_key(key(KeyCode::Home));
+    editor.handle_key(key(KeyCode::Delete));
+    assert_eq!(editor.text(), "bc");
+    editor.handle_key(key(KeyCode::End));
+    editor.handle_key(key(KeyCode::Backspace));
+    assert_eq!(editor.text(), "b");
+
+    // Keys that are not for editing are left to the caller
+    assert!(!editor.handle_key(key(KeyCode::Enter)));
+    assert!(!editor.handle_key(key(KeyCode::Esc)));
+}
+
+#[test]
+fn test_line_editor_unicode() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+    // A combining accent is deleted together with the letter before it
+    let mut editor = typed("cafe\u{301}");
+    editor.handle_key(key(KeyCode::Backspace));
+    assert_eq!(editor.text(), "caf");
+
+    // Moving over wide characters and deleting at the cursor
+    editor = typed("日本語");
+    editor.handle_key(key(KeyCode::Left));
+    editor.handle_key(key(KeyCode::Left));
+    editor.handle_key(key(KeyCode::Backspace));
+    assert_eq!(editor.text(), "本語");
+    assert_eq!(editor.visible(80), ("本語", 0));
+}
+
+#[test]
+fn test_line_editor_scrolling() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+    let mut editor = typed("0123456789");
+    // The end of the text is shown, with a column left for the cursor
+    assert_eq!(editor.visible(5), ("6789", 4));
+    // The view only scrolls when the cursor leaves it
+    editor.handle_key(key(KeyCode::Left));
+    assert_eq!(editor.visible(5), ("6789", 3));
+    editor.handle_key(key(KeyCode::Home));
+    assert_eq!(editor.visible(5), ("0123", 0));
+    editor.handle_key(key(KeyCode::Right));
+    assert_eq!(editor.visible(5), ("0123", 1));
+    // Wide characters are counted by their width
+    editor = typed("日本語");
+    assert_eq!(editor.visible(5), ("本語", 4));
+}
+
+#[test]
+fn test_line_editor_history() {
+    let history = ["first".to_string(), "second".to_string()];
+    let mut editor = typed("draft");
+
+    assert!(editor.history_prev(&history));
+    assert_eq!(editor.text(), "second");
+    assert!(editor.history_prev(&history));
+    assert_eq!(editor.text(), "first");
+    // There is nothing older
+    assert!(!editor.history_prev(&history));
+    assert_eq!(editor.text(), "first");
+
+    assert!(editor.history_next(&history));
+    assert_eq!(editor.text(), "second");
+    // Going past the most recent entry brings back what was typed
+    assert!(editor.history_next(&history));
+    assert_eq!(editor.text(), "draft");
+    assert!(!editor.history_next(&history));
+
+    assert!(!typed("").history_prev(&[]));
+}
+
+#[cfg(feature = "search")]
+#[test]
+fn test_search_history() {
+    use super::history::SearchHistory;
+    use crate::SearchMode;
+
+    let mut history = SearchHistory::default();
+    history.add(SearchMode::Forward, "foo".to_string());
+    history.add(SearchMode::Forward, "bar".to_string());
+    history.add(SearchMode::Reverse, "baz".to_string());
+    // Adding a query again moves it to the end instead of duplicating it
+    history.add(SearchMode::Forward, "foo".to_string());
+    history.add(SearchMode::Forward, String::new());
+    assert_eq!(history.entries(SearchMode::Forward), ["bar", "foo"]);
+    assert_eq!(history.entries(SearchMode::Reverse), ["baz"]);
+
+    // The oldest entries are dropped over the limit
+    history.set_limit(1);
+    assert_eq!(history.entries(SearchMode::Forward), ["foo"]);
+    history.add(SearchMode::Forward, "new".to_string());
+    assert_eq!(history.entries(SearchMode::Forward), ["new"]);
+}
+
+#[cfg(feature = "search")]
+#[test]
+fn test_search_history_file() {
+    use super::history::SearchHistory;
+    use crate::SearchMode;
+
+    let mut history = SearchHistory::default();
+    history.add(SearchMode::Forward, "a.b".to_string());
+    history.add(SearchMode::Forward, "\"quoted\"".to_string());
+    history.add(SearchMode::Reverse, "back".to_string());
+    let text = history.serialize();
+    assert_eq!(
+        text,
+        ".minus-history-file:\n.search\n\"a.b\n\"\"quoted\"\n.search-reverse\n\"back\n"
+    );
+
+    let mut parsed = SearchHistory::default();
+    parsed.parse(&text);
+    assert_eq!(parsed, history);
+
+    // Unknown sections are skipped
+    let mut parsed = SearchHistory::default();
+    parsed.parse(".minus-history-file:\n.shell\n\"ls\n.search\n\"x\n");
+    assert_eq!(parsed.entries(SearchMode::Forward), ["x"]);
+    assert!(parsed.entries(SearchMode::Reverse).is_empty());
+
+    // Round trip through a file, which does not exist at first
+    let path = std::env::temp_dir().join(format!("minus-history-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut saved = SearchHistory::default();
+    saved.set_path(path.clone()).unwrap();
+    saved.add(SearchMode::Reverse, "saved".to_string());
+    saved.save().unwrap();
+    let mut loaded = SearchHistory::default();
+    loaded.set_path(path.clone()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.entries(SearchMode::Reverse), ["saved"]);
+}
+
+#[cfg(feature = "search")]
+#[test]
+fn draw_filter_line_numbers() {
+    let mut pager = Pager::new().unwrap();
+    pager.set_text("one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\neleven");
+    pager.set_line_numbers(LineNumbers::Enabled);
+    pager.set_filter("e$", false).unwrap();
+    let mut out = Vec::new();
+    write_lines(&mut out, &mut pager).unwrap();
+    // The numbers of the lines in the whole output are shown
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "\r  1. one\n\r  3. three\n\r  5. five\n\r  9. nine\n"
+    );
+}
+
+#[test]
+fn test_paste_detection() {
+    use super::prompt::PasteDetector;
+    use std::time::{Duration, Instant};
+
+    let start = Instant::now();
+    let at = |ms| start + Duration::from_millis(ms);
+
+    // Typed keys are too far apart to be pasted
+    let mut paste = PasteDetector::default();
+    for ms in [0, 150, 300] {
+        paste.key_read(at(ms));
+    }
+    assert!(!paste.is_pasted_newline(false));
+    assert!(!paste.is_pasted_newline(true));
+
+    // A newline in a burst of keys is pasted, even at it's end
+    let mut paste = PasteDetector::default();
+    for ms in [0, 1, 2, 3] {
+        paste.key_read(at(ms));
+    }
+    assert!(paste.is_pasted_newline(false));
+
+    // A key and Enter read together accept the input, unless more keys follow
+    let mut paste = PasteDetector::default();
+    paste.key_read(at(0));
+    paste.key_read(at(100));
+    paste.key_read(at(101));
+    assert!(!paste.is_pasted_newline(false));
+    assert!(paste.is_pasted_newline(true));
+}